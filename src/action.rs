@@ -0,0 +1,500 @@
+///----------action.rs----------///
+
+use crate::{utils, App, InputMode};
+use serde::{Deserialize, Serialize};
+
+/// Everything the user can ask the application to do, independent of the keys that trigger it.
+/// Variants that are bound in `KeyBindings` share their name with the binding.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    /// Moves the selection up in the list or popup being shown.
+    ListUp,
+    /// Moves the selection down in the list or popup being shown.
+    ListDown,
+    /// Jumps to the first task, or to task N with a count.
+    ListTop,
+    /// Jumps to the last task, or to task N with a count.
+    ListBottom,
+    /// Checks or unchecks the target tasks.
+    CheckAndUncheckTask,
+    /// Saves everything and quits.
+    ExitApp,
+    /// Starts typing a new task, added at the configured insert position.
+    NewTask,
+    /// Starts typing a new task, added above the selection.
+    NewTaskAbove,
+    /// Starts typing a new task, added below the selection.
+    NewTaskBelow,
+    /// Moves the target tasks to the trash.
+    DeleteTask,
+    /// Moves the target tasks one position up.
+    MoveTaskUp,
+    /// Moves the target tasks one position down.
+    MoveTaskDown,
+    /// Moves the target tasks to the top of the list.
+    MoveTaskTop,
+    /// Moves the target tasks to the bottom of the list.
+    MoveTaskBottom,
+    /// Starts editing the title of the selected task.
+    EditTask,
+    /// Undoes the most recent change.
+    Undo,
+    /// Redoes the most recently undone change.
+    Redo,
+    /// Opens the trash.
+    ViewTrash,
+    /// Puts the selected task in the trash back into the list.
+    RestoreTask,
+    /// Permanently deletes the selected task in the trash.
+    PurgeTask,
+    /// Permanently deletes every task in the trash.
+    EmptyTrash,
+    /// Moves every completed task to the archive.
+    ArchiveCompleted,
+    /// Opens the archive.
+    ViewArchive,
+    /// Opens or closes the statistics screen.
+    ViewStats,
+    /// Starts searching the task list, or the archive while it is shown.
+    Search,
+    /// Selects the next task matching the search.
+    SearchNext,
+    /// Selects the previous task matching the search.
+    SearchPrevious,
+    /// Returns from the trash, archive, statistics or a popup to the task list.
+    ExitView,
+    /// Marks or unmarks the selected task for a batch operation.
+    ToggleMark,
+    /// Starts or ends marking a range of tasks.
+    VisualMode,
+    /// Unmarks every task.
+    ClearMarks,
+    /// Sets the target tasks to the next priority.
+    CyclePriority,
+    /// Starts typing a tag to add to or remove from the target tasks.
+    TagTask,
+    /// Abandons what is being typed in the active input field.
+    ExitAddingMode,
+    /// Saves what is being typed in the active input field.
+    SaveTask,
+    /// Answers yes to the confirmation prompt.
+    Confirm,
+    /// Answers no to the confirmation prompt, or leaves any other mode.
+    Cancel,
+    /// Moves the input cursor one character left.
+    InputCursorLeft,
+    /// Moves the input cursor one character right.
+    InputCursorRight,
+    /// Moves the input cursor to the start of the input.
+    InputStart,
+    /// Moves the input cursor to the end of the input.
+    InputEnd,
+    /// Deletes the input from the cursor to the end.
+    InputKillLine,
+    /// Saves everything without quitting.
+    SaveToDisk,
+    /// Opens or closes the list of key bindings.
+    Help,
+    /// Opens the command palette.
+    CommandPalette,
+    /// Opens or closes the history of status line messages.
+    MessageHistory,
+    /// Switches to the next theme.
+    CycleTheme,
+    /// Shows or hides the details of the selected task.
+    ToggleDetails,
+    /// Scrolls the task details up.
+    ScrollDetailsUp,
+    /// Scrolls the task details down.
+    ScrollDetailsDown,
+    /// Selects the previous command in the palette.
+    PreviousCommand,
+    /// Selects the next command in the palette.
+    NextCommand,
+    /// Types a character into the active input field.
+    InsertChar(char),
+    /// Erases the last character of the active input field.
+    DeleteChar,
+}
+
+impl Action {
+    /// Returns the name of the action as written in the config file, e.g. `list_up`.
+    pub fn name(self) -> String {
+        match serde_json::to_value(self) {
+            Ok(serde_json::Value::String(name)) => name,
+            _ => format!("{:?}", self),
+        }
+    }
+
+    /// Returns whether the action applies to the marked tasks when there are any, instead of the selected task.
+    pub fn is_batch(self) -> bool {
+        matches!(
+            self,
+            Action::CheckAndUncheckTask
+                | Action::CyclePriority
+                | Action::MoveTaskUp
+                | Action::MoveTaskDown
+                | Action::MoveTaskTop
+                | Action::MoveTaskBottom
+                | Action::DeleteTask
+                | Action::TagTask
+        )
+    }
+
+    /// Returns a short description of what the action does, as shown in help.
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::ListUp => "Move up",
+            Action::ListDown => "Move down",
+            Action::ListTop => "Go to the top",
+            Action::ListBottom => "Go to the bottom",
+            Action::CheckAndUncheckTask => "Check/uncheck task",
+            Action::ExitApp => "Exit",
+            Action::NewTask => "Add task",
+            Action::NewTaskAbove => "Add task above selection",
+            Action::NewTaskBelow => "Add task below selection",
+            Action::DeleteTask => "Delete task",
+            Action::MoveTaskUp => "Move task up",
+            Action::MoveTaskDown => "Move task down",
+            Action::MoveTaskTop => "Move task to the top",
+            Action::MoveTaskBottom => "Move task to the bottom",
+            Action::EditTask => "Edit task",
+            Action::Undo => "Undo",
+            Action::Redo => "Redo",
+            Action::ViewTrash => "Open the trash",
+            Action::RestoreTask => "Restore task",
+            Action::PurgeTask => "Purge task",
+            Action::EmptyTrash => "Empty the trash",
+            Action::ArchiveCompleted => "Archive completed tasks",
+            Action::ViewArchive => "Open the archive",
+            Action::ViewStats => "Show/hide statistics",
+            Action::Search => "Search",
+            Action::SearchNext => "Next match",
+            Action::SearchPrevious => "Previous match",
+            Action::ExitView => "Back",
+            Action::ToggleMark => "Mark/unmark task",
+            Action::VisualMode => "Mark a range",
+            Action::ClearMarks => "Clear marks",
+            Action::CyclePriority => "Cycle priority",
+            Action::TagTask => "Add/remove tag",
+            Action::ExitAddingMode => "Cancel",
+            Action::SaveTask => "Save",
+            Action::Confirm => "Confirm",
+            Action::Cancel => "Cancel",
+            Action::InputCursorLeft => "Cursor left",
+            Action::InputCursorRight => "Cursor right",
+            Action::InputStart => "Cursor to start",
+            Action::InputEnd => "Cursor to end",
+            Action::InputKillLine => "Delete to end",
+            Action::SaveToDisk => "Save to disk",
+            Action::Help => "Show/hide help",
+            Action::CommandPalette => "Open the command palette",
+            Action::MessageHistory => "Show/hide message history",
+            Action::CycleTheme => "Switch to the next theme",
+            Action::ToggleDetails => "Show/hide task details",
+            Action::ScrollDetailsUp => "Scroll task details up",
+            Action::ScrollDetailsDown => "Scroll task details down",
+            Action::PreviousCommand => "Previous command",
+            Action::NextCommand => "Next command",
+            Action::InsertChar(_) => "Type a character",
+            Action::DeleteChar => "Erase a character",
+        }
+    }
+}
+
+/// An action held back until the user answers the confirmation prompt.
+#[derive(Clone, Debug)]
+pub struct Confirmation {
+    /// Question shown in the confirmation dialog.
+    pub prompt: String,
+    /// Action performed if the user answers yes.
+    pub action: Action,
+    /// Repeat count typed before the action.
+    pub count: Option<usize>,
+    /// Mode the action was requested in, returned to whatever the answer.
+    pub origin: InputMode,
+}
+
+/// Performs `action` as many times as the typed count says, first asking for confirmation if the settings require
+/// it for this action.
+pub fn dispatch_counted(app: &mut App, action: Action, count: Option<usize>) {
+    match confirmation_prompt(app, action, count) {
+        Some(prompt) => utils::ask_confirmation(app, Confirmation { prompt, action, count, origin: app.input_mode }),
+        None => repeat(app, action, count),
+    }
+}
+
+/// Performs `action`, first asking for confirmation if the settings require it for this action. Actions that exist
+/// in several modes, such as moving the selection, act on whatever the current mode is showing.
+pub fn dispatch(app: &mut App, action: Action) {
+    dispatch_counted(app, action, None);
+}
+
+/// Performs the action waiting for confirmation, in the mode it was requested in.
+fn confirm(app: &mut App) {
+    if let Some(confirmation) = app.confirmation.take() {
+        app.input_mode = confirmation.origin;
+        repeat(app, confirmation.action, confirmation.count);
+    }
+}
+
+/// Returns the question to ask before performing `action` `count` times, if the settings require confirmation
+/// for it. Destructive actions are only confirmed when they would change something.
+fn confirmation_prompt(app: &App, action: Action, count: Option<usize>) -> Option<String> {
+    let confirm = &app.config.settings.confirm;
+    let targets = app.target_count();
+    let repeats = count.unwrap_or(1).max(1);
+    let in_list = matches!(app.input_mode, InputMode::Normal | InputMode::Visual);
+    let plural = |count: usize| if count == 1 { "" } else { "s" };
+    let tasks = |count: usize| format!("{} task{}", count, plural(count));
+    let times = if repeats > 1 { format!(" {} times", repeats) } else { String::new() };
+
+    match action {
+        Action::ExitApp if confirm.quit => Some("Quit?".to_string()),
+        // The first deletion takes the marked tasks with it, and every further one the newly selected task.
+        Action::DeleteTask if in_list && targets > 0 => {
+            let deleted = (targets + repeats - 1).min(app.task.len());
+            (confirm.delete || (confirm.bulk && deleted > 1)).then(|| format!("Delete {}?", tasks(deleted)))
+        }
+        Action::PurgeTask if confirm.delete && app.selected_trash.is_some() => {
+            Some("Permanently delete this task?".to_string())
+        }
+        Action::EmptyTrash if confirm.delete && !app.trash.is_empty() => Some(format!(
+            "Permanently delete {} task{} in the trash?",
+            app.trash.len(),
+            plural(app.trash.len())
+        )),
+        Action::ArchiveCompleted if confirm.clear_completed => {
+            let completed = app.task.iter().filter(|task| task.completed).count();
+            (completed > 0).then(|| format!("Archive {} completed task{}?", completed, plural(completed)))
+        }
+        // Repeating these changes the same tasks again, so the count says how often rather than how many.
+        Action::CheckAndUncheckTask
+        | Action::CyclePriority
+        | Action::MoveTaskUp
+        | Action::MoveTaskDown
+        | Action::MoveTaskTop
+        | Action::MoveTaskBottom
+            if in_list && confirm.bulk && targets > 0 && (targets > 1 || repeats > 1) =>
+        {
+            let tasks = tasks(targets);
+            Some(match action {
+                Action::CheckAndUncheckTask => format!("Check/uncheck {}{}?", tasks, times),
+                Action::CyclePriority => format!("Change the priority of {}{}?", tasks, times),
+                Action::MoveTaskUp => format!("Move {} up{}?", tasks, times),
+                Action::MoveTaskDown => format!("Move {} down{}?", tasks, times),
+                Action::MoveTaskTop => format!("Move {} to the top?", tasks),
+                _ => format!("Move {} to the bottom?", tasks),
+            })
+        }
+        Action::SaveTask if app.input_mode == InputMode::Tagging && confirm.bulk && targets > 1 => {
+            let tag = utils::tag_name(&app.input);
+            let prompt = if utils::tag_removes(app, tag) {
+                format!("Remove #{} from {}?", tag, tasks(targets))
+            } else {
+                format!("Add #{} to {}?", tag, tasks(targets))
+            };
+            (!tag.is_empty()).then_some(prompt)
+        }
+        _ => None,
+    }
+}
+
+/// Performs `action` as many times as `count` says, stopping early if it switches to another mode. Jumping to the
+/// top or bottom with a count goes to that task number instead, and repeated changes are undone in one step. The
+/// marks are cleared once a batch operation has been performed on them.
+fn repeat(app: &mut App, action: Action, count: Option<usize>) {
+    let count = match (action, count) {
+        (Action::ListTop | Action::ListBottom, Some(number)) => return utils::go_to_task(app, number),
+        (_, count) => count.unwrap_or(1),
+    };
+
+    let mode = app.input_mode;
+    app.history.begin_group();
+    for _ in 0..count {
+        perform(app, action);
+        if app.input_mode != mode {
+            break;
+        }
+    }
+    app.history.end_group();
+
+    // Tagging only applies to the marked tasks once the tag has been typed, and clears the marks then.
+    if action.is_batch() && action != Action::TagTask {
+        utils::clear_marks(app);
+    }
+}
+
+/// Performs `action` without asking for confirmation.
+fn perform(app: &mut App, action: Action) {
+    let mode = app.input_mode;
+
+    match action {
+        Action::ListUp => match mode {
+            InputMode::Trash => utils::trash_up(app),
+            InputMode::Archive => utils::archive_up(app),
+            InputMode::Help | InputMode::Messages => utils::popup_scroll(app, -1),
+            _ => utils::list_up(app),
+        },
+        Action::ListDown => match mode {
+            InputMode::Trash => utils::trash_down(app),
+            InputMode::Archive => utils::archive_down(app),
+            InputMode::Help | InputMode::Messages => utils::popup_scroll(app, 1),
+            _ => utils::list_down(app),
+        },
+        Action::ListTop => match mode {
+            InputMode::Help | InputMode::Messages => utils::popup_scroll(app, isize::MIN),
+            _ => utils::list_top(app),
+        },
+        Action::ListBottom => match mode {
+            InputMode::Help | InputMode::Messages => utils::popup_scroll(app, isize::MAX),
+            _ => utils::list_bottom(app),
+        },
+        Action::CheckAndUncheckTask => utils::check_and_uncheck_task(app),
+        Action::ExitApp => utils::exit_app(app),
+        Action::NewTask => utils::new_task(app),
+        Action::NewTaskAbove => utils::new_task_above(app),
+        Action::NewTaskBelow => utils::new_task_below(app),
+        Action::DeleteTask => utils::delete_task(app),
+        Action::MoveTaskUp => utils::move_task_up(app),
+        Action::MoveTaskDown => utils::move_task_down(app),
+        Action::MoveTaskTop => utils::move_task_top(app),
+        Action::MoveTaskBottom => utils::move_task_bottom(app),
+        Action::EditTask => utils::edit_task(app),
+        Action::Undo => utils::undo(app),
+        Action::Redo => utils::redo(app),
+        Action::ViewTrash => utils::view_trash(app),
+        Action::RestoreTask => utils::restore_task(app),
+        Action::PurgeTask => utils::purge_task(app),
+        Action::EmptyTrash => utils::empty_trash(app),
+        Action::ArchiveCompleted => utils::archive_completed(app),
+        Action::ViewArchive => utils::view_archive(app),
+        Action::ViewStats => utils::toggle_stats(app),
+        Action::Search => match mode {
+            InputMode::Archive => utils::start_archive_search(app),
+            _ => utils::start_task_search(app),
+        },
+        Action::SearchNext => utils::search_next(app),
+        Action::SearchPrevious => utils::search_previous(app),
+        Action::ExitView => match mode {
+            InputMode::Visual => utils::exit_visual_mode(app),
+            InputMode::Help | InputMode::Messages => utils::toggle_popup(app, mode),
+            _ => utils::exit_view(app),
+        },
+        Action::ToggleMark => utils::toggle_mark(app),
+        Action::VisualMode => match mode {
+            InputMode::Visual => utils::toggle_mark(app),
+            _ => utils::visual_mode(app),
+        },
+        Action::ClearMarks => utils::clear_marks(app),
+        Action::CyclePriority => utils::cycle_priority(app),
+        Action::TagTask => utils::tag_task(app),
+        Action::ExitAddingMode => match mode {
+            InputMode::Tagging => utils::exit_tagging_mode(app),
+            InputMode::ArchiveSearch => utils::cancel_archive_search(app),
+            InputMode::Search => utils::cancel_task_search(app),
+            InputMode::Palette => utils::close_palette(app),
+            _ => utils::exit_adding_mode(app),
+        },
+        Action::SaveTask => match mode {
+            InputMode::Tagging => utils::save_tag(app),
+            InputMode::ArchiveSearch => utils::finish_archive_search(app),
+            InputMode::Search => utils::finish_task_search(app),
+            InputMode::Palette => utils::run_palette(app),
+            _ if !app.input.trim().is_empty() => utils::save_task(app),
+            _ => {}
+        },
+        Action::Confirm => confirm(app),
+        Action::Cancel => utils::cancel(app),
+        Action::InsertChar(c) => utils::input_add_char(app, c),
+        Action::DeleteChar => utils::input_del_char(app),
+        Action::InputCursorLeft => utils::input_cursor_left(app),
+        Action::InputCursorRight => utils::input_cursor_right(app),
+        Action::InputStart => utils::input_start(app),
+        Action::InputEnd => utils::input_end(app),
+        Action::InputKillLine => utils::input_kill_line(app),
+        Action::SaveToDisk => utils::save_to_disk(app),
+        Action::Help => utils::toggle_popup(app, InputMode::Help),
+        Action::MessageHistory => utils::toggle_popup(app, InputMode::Messages),
+        Action::CycleTheme => utils::cycle_theme(app),
+        Action::ToggleDetails => utils::toggle_details(app),
+        Action::ScrollDetailsUp => utils::scroll_details(app, -1),
+        Action::ScrollDetailsDown => utils::scroll_details(app, 1),
+        Action::CommandPalette => utils::open_palette(app),
+        Action::PreviousCommand => utils::palette_up(app),
+        Action::NextCommand => utils::palette_down(app),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::Config, test_tasks};
+
+    /// Returns an app with the `titles` tasks that asks before changing several tasks at once.
+    fn app(titles: &[&str]) -> App {
+        let mut config = Config::default();
+        config.settings.confirm.bulk = true;
+        App::new(&test_tasks(titles), config)
+    }
+
+    #[test]
+    fn counted_deletion_asks_about_the_tasks_it_deletes() {
+        let mut app = app(&["a", "b", "c", "d", "e"]);
+        for index in [0, 1, 2] {
+            app.task[index].marked = true;
+        }
+
+        dispatch_counted(&mut app, Action::DeleteTask, Some(2));
+        assert_eq!(app.confirmation.as_ref().unwrap().prompt, "Delete 4 tasks?");
+
+        dispatch(&mut app, Action::Confirm);
+        assert_eq!(app.task.len(), 1);
+    }
+
+    #[test]
+    fn repeated_changes_say_how_often() {
+        let mut app = app(&["a", "b", "c"]);
+
+        assert_eq!(confirmation_prompt(&app, Action::CheckAndUncheckTask, None), None);
+        assert_eq!(
+            confirmation_prompt(&app, Action::MoveTaskDown, Some(2)),
+            Some("Move 1 task down 2 times?".to_string())
+        );
+
+        app.task[0].marked = true;
+        app.task[1].marked = true;
+        assert_eq!(
+            confirmation_prompt(&app, Action::CheckAndUncheckTask, None),
+            Some("Check/uncheck 2 tasks?".to_string())
+        );
+    }
+
+    #[test]
+    fn tagging_marked_tasks_asks_whether_it_adds_or_removes() {
+        let mut app = app(&["a", "b", "c"]);
+        app.task[0].marked = true;
+        app.task[2].marked = true;
+
+        dispatch(&mut app, Action::TagTask);
+        app.input = "#work".to_string();
+        dispatch(&mut app, Action::SaveTask);
+        assert_eq!(app.input_mode, InputMode::Confirm);
+        assert_eq!(app.confirmation.as_ref().unwrap().prompt, "Add #work to 2 tasks?");
+
+        dispatch(&mut app, Action::Confirm);
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert_eq!(app.task[0].tags, ["work"]);
+        assert!(app.task[1].tags.is_empty());
+        assert_eq!(app.task[2].tags, ["work"]);
+        assert!(app.task.iter().all(|task| !task.marked));
+
+        app.task[0].marked = true;
+        app.task[2].marked = true;
+        dispatch(&mut app, Action::TagTask);
+        app.input = "work".to_string();
+        dispatch(&mut app, Action::SaveTask);
+        assert_eq!(app.confirmation.as_ref().unwrap().prompt, "Remove #work from 2 tasks?");
+    }
+}
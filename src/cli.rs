@@ -0,0 +1,103 @@
+///----------cli.rs----------///
+
+use crate::{
+    config::Config,
+    file_handler::{load_config, load_task, load_trash, save_history, save_task, save_trash},
+    history::History,
+    theme::{Theme, BUILTIN_THEMES},
+    utils::format_age,
+    DynResult,
+};
+
+const USAGE: &str = "\
+Usage:
+  todo-list                        Start the interactive task list
+  todo-list trash list             List the tasks in the trash
+  todo-list trash restore <n>      Restore task number <n> from the trash
+  todo-list trash purge <n>        Permanently delete task number <n> from the trash
+  todo-list trash empty            Permanently delete every task in the trash
+  todo-list config default         Print the default config file
+  todo-list theme <name>           Print a built-in theme, to start a theme file from";
+
+/// Runs a one-off command given on the command line.
+pub fn run(args: &[String]) -> DynResult {
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    match args.as_slice() {
+        ["trash", rest @ ..] => run_trash(rest),
+        ["config", "default"] => {
+            println!("{}", serde_json::to_string_pretty(&Config::default())?);
+            Ok(())
+        }
+        ["theme", name] => match Theme::builtin(name) {
+            Some(theme) => {
+                println!("{}", serde_json::to_string_pretty(&theme)?);
+                Ok(())
+            }
+            None => Err(format!("Unknown theme {}, use one of: {}", name, BUILTIN_THEMES.join(", ")).into()),
+        },
+        ["help"] | ["--help"] | ["-h"] => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        _ => Err(format!("Unknown command\n\n{}", USAGE).into()),
+    }
+}
+
+/// Handles the `trash` subcommands. The trash is saved before the task list, so that a failed write never leaves
+/// a restored task in both, and a change is only reported once everything has been written.
+fn run_trash(args: &[&str]) -> DynResult {
+    let config = load_config()?;
+    let mut trash = load_trash(config.settings.trash_retention_days)?;
+
+    let (restored, report) = match args {
+        [] | ["list"] => {
+            if trash.is_empty() {
+                println!("The trash is empty");
+            }
+            for (number, trashed) in trash.iter().enumerate() {
+                println!("{:>3}. {:>9}  {}", number + 1, format_age(trashed.deleted_at), trashed.task.title);
+            }
+            // Saving also writes back any tasks dropped by the retention period.
+            return save_trash(&trash);
+        }
+        ["restore", number] => {
+            let trashed = trash.remove(trash_index(number, trash.len())?);
+            let report = format!("Restored \"{}\"", trashed.task.title);
+            (Some(trashed.task), report)
+        }
+        ["purge", number] => {
+            let trashed = trash.remove(trash_index(number, trash.len())?);
+            (None, format!("Purged \"{}\"", trashed.task.title))
+        }
+        ["empty"] => {
+            let report = format!("Purged {} task(s)", trash.len());
+            trash.clear();
+            (None, report)
+        }
+        _ => return Err(format!("Unknown trash command\n\n{}", USAGE).into()),
+    };
+
+    let task = match restored {
+        Some(restored) => Some(load_task()?.into_iter().chain([restored]).collect::<Vec<_>>()),
+        None => None,
+    };
+    save_trash(&trash)?;
+    if let Some(task) = task {
+        save_task(&task)?;
+    }
+
+    // The saved undo history was taken before this change, so undoing from it would bring back what was changed.
+    save_history(&History::default(), 0)?;
+    println!("{}", report);
+
+    Ok(())
+}
+
+/// Converts a 1-based task number as shown by `trash list` into an index into the trash.
+fn trash_index(number: &str, len: usize) -> Result<usize, String> {
+    match number.parse::<usize>() {
+        Ok(number) if number >= 1 && number <= len => Ok(number - 1),
+        _ => Err(format!("No task number {} in the trash", number)),
+    }
+}
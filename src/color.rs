@@ -0,0 +1,308 @@
+///----------color.rs----------///
+
+use crate::config::ColorSupport;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::env;
+use tui::style::Color;
+
+/// The 16 basic ANSI colors with the RGB values xterm shows them in, in palette order.
+const ANSI_COLORS: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+/// Names a color can be given by in config and theme files, in palette order after the terminal's own colors.
+const COLOR_NAMES: [(&str, Color); 17] = [
+    ("reset", Color::Reset),
+    ("black", Color::Black),
+    ("red", Color::Red),
+    ("green", Color::Green),
+    ("yellow", Color::Yellow),
+    ("blue", Color::Blue),
+    ("magenta", Color::Magenta),
+    ("cyan", Color::Cyan),
+    ("gray", Color::Gray),
+    ("dark_gray", Color::DarkGray),
+    ("light_red", Color::LightRed),
+    ("light_green", Color::LightGreen),
+    ("light_yellow", Color::LightYellow),
+    ("light_blue", Color::LightBlue),
+    ("light_magenta", Color::LightMagenta),
+    ("light_cyan", Color::LightCyan),
+    ("white", Color::White),
+];
+
+/// Levels of each channel in the 6×6×6 color cube of the 256-color palette.
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// Resolves `Auto` to what the terminal supports, going by the environment variables terminals and users set.
+/// Any value of `NO_COLOR` turns colors off, as <https://no-color.org> asks.
+pub fn detect(support: ColorSupport) -> ColorSupport {
+    if support != ColorSupport::Auto {
+        return support;
+    }
+
+    let var = |name| env::var(name).unwrap_or_default().to_lowercase();
+    if !var("NO_COLOR").is_empty() {
+        return ColorSupport::None;
+    }
+
+    let colorterm = var("COLORTERM");
+    let term = var("TERM");
+    if colorterm == "truecolor" || colorterm == "24bit" || term.ends_with("-direct") {
+        ColorSupport::Truecolor
+    } else if term == "dumb" {
+        ColorSupport::None
+    } else if term.contains("256color") {
+        ColorSupport::Ansi256
+    } else if term.is_empty() && cfg!(windows) {
+        // The Windows console does not set TERM but has shown RGB colors since Windows 10.
+        ColorSupport::Truecolor
+    } else {
+        ColorSupport::Ansi16
+    }
+}
+
+/// Replaces `color` by the nearest color the terminal can show. Without color support every color is reset to
+/// the terminal's own.
+pub fn downgrade(color: Color, support: ColorSupport) -> Color {
+    match (support, color) {
+        (ColorSupport::None, _) => Color::Reset,
+        (ColorSupport::Ansi256, Color::Rgb(r, g, b)) => Color::Indexed(nearest_indexed((r, g, b))),
+        (ColorSupport::Ansi16, Color::Rgb(r, g, b)) => nearest_ansi((r, g, b)),
+        (ColorSupport::Ansi16, Color::Indexed(index)) => nearest_ansi(indexed_rgb(index)),
+        _ => color,
+    }
+}
+
+/// Returns the RGB value of a color of the 256-color palette.
+fn indexed_rgb(index: u8) -> (u8, u8, u8) {
+    match index {
+        0..=15 => ANSI_COLORS[index as usize].1,
+        16..=231 => {
+            let index = index - 16;
+            (
+                CUBE_LEVELS[(index / 36) as usize],
+                CUBE_LEVELS[(index / 6 % 6) as usize],
+                CUBE_LEVELS[(index % 6) as usize],
+            )
+        }
+        _ => {
+            let level = 8 + 10 * (index - 232);
+            (level, level, level)
+        }
+    }
+}
+
+/// Returns the index of the color cube or gray ramp entry nearest to `rgb`. The first 16 entries are left out
+/// because terminals let users change them.
+fn nearest_indexed(rgb: (u8, u8, u8)) -> u8 {
+    (16..=255).min_by_key(|&index| distance(rgb, indexed_rgb(index))).unwrap_or(16)
+}
+
+/// Returns the basic ANSI color nearest to `rgb`.
+fn nearest_ansi(rgb: (u8, u8, u8)) -> Color {
+    ANSI_COLORS
+        .iter()
+        .min_by_key(|(_, ansi)| distance(rgb, *ansi))
+        .map_or(Color::Reset, |(color, _)| *color)
+}
+
+/// Squared distance between two colors, weighting the channels by how sensitive the eye is to each.
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let channel = |a: u8, b: u8, weight: u32| weight * (a as i32 - b as i32).pow(2) as u32;
+    channel(a.0, b.0, 3) + channel(a.1, b.1, 4) + channel(a.2, b.2, 2)
+}
+
+/// Parses a color written as `#rrggbb` or `#rgb`, `rgb(r, g, b)`, a color name such as `light_red` or a palette
+/// index from 0 to 255. Names ignore case, dashes and underscores, so `LightRed` and `light-red` also work.
+pub fn parse(text: &str) -> Result<Color, String> {
+    let text = text.trim();
+    let invalid = || {
+        format!(
+            "invalid color \"{}\", expected \"#rrggbb\", \"rgb(r, g, b)\", a color name such as \"light_red\" or a \
+             palette index from 0 to 255",
+            text
+        )
+    };
+
+    if let Some(hex) = text.strip_prefix('#') {
+        let digits: Vec<u8> = hex
+            .chars()
+            .map(|digit| digit.to_digit(16).map(|digit| digit as u8))
+            .collect::<Option<_>>()
+            .ok_or_else(invalid)?;
+        return match digits[..] {
+            [r, g, b] => Ok(Color::Rgb(r * 17, g * 17, b * 17)),
+            [r1, r2, g1, g2, b1, b2] => Ok(Color::Rgb(r1 * 16 + r2, g1 * 16 + g2, b1 * 16 + b2)),
+            _ => Err(invalid()),
+        };
+    }
+
+    if let Some(channels) = text.strip_prefix("rgb(").and_then(|rest| rest.strip_suffix(')')) {
+        let channels: Vec<u8> = channels
+            .split(',')
+            .map(|channel| channel.trim().parse().ok())
+            .collect::<Option<_>>()
+            .ok_or_else(invalid)?;
+        return match channels[..] {
+            [r, g, b] => Ok(Color::Rgb(r, g, b)),
+            _ => Err(invalid()),
+        };
+    }
+
+    if let Ok(index) = text.parse::<u8>() {
+        return Ok(Color::Indexed(index));
+    }
+
+    let simplified = |name: &str| name.replace(['_', '-'], "").to_lowercase();
+    COLOR_NAMES
+        .iter()
+        .find(|(name, _)| simplified(name) == simplified(text))
+        .map(|(_, color)| *color)
+        .ok_or_else(invalid)
+}
+
+/// Formats a color the way `parse` reads it: RGB colors in hex, palette colors by index and the rest by name.
+pub fn format(color: Color) -> String {
+    match color {
+        Color::Rgb(r, g, b) => format!("#{:02x}{:02x}{:02x}", r, g, b),
+        Color::Indexed(index) => index.to_string(),
+        _ => COLOR_NAMES
+            .iter()
+            .find(|(_, named)| *named == color)
+            .map_or_else(|| "reset".to_string(), |(name, _)| name.to_string()),
+    }
+}
+
+/// The forms a color can take in a file. `Legacy` is how colors were written before they could be given as text,
+/// for example `{"Rgb": [242, 60, 147]}`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ColorRepr {
+    Text(String),
+    Index(u8),
+    Legacy(Color),
+}
+
+/// Serde functions for optional colors in config and theme files, used with `#[serde(with = "color::optional")]`.
+pub mod optional {
+    use super::*;
+
+    /// Writes the color in the form `parse` reads.
+    pub fn serialize<S: Serializer>(color: &Option<Color>, serializer: S) -> Result<S::Ok, S::Error> {
+        color.map(format).serialize(serializer)
+    }
+
+    /// Reads a color in any of the forms `parse` accepts, or in the legacy form.
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Color>, D::Error> {
+        let repr = Option::<ColorRepr>::deserialize(deserializer).map_err(|_| {
+            de::Error::custom("invalid color, expected a string such as \"#f23c93\" or a palette index from 0 to 255")
+        })?;
+
+        match repr {
+            None => Ok(None),
+            Some(ColorRepr::Text(text)) => parse(&text).map(Some).map_err(de::Error::custom),
+            Some(ColorRepr::Index(index)) => Ok(Some(Color::Indexed(index))),
+            Some(ColorRepr::Legacy(color)) => Ok(Some(color)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_hex_colors() {
+        assert_eq!(parse("#f23c93"), Ok(Color::Rgb(242, 60, 147)));
+        assert_eq!(parse("#F23C93"), Ok(Color::Rgb(242, 60, 147)));
+        assert_eq!(parse("#f39"), Ok(Color::Rgb(255, 51, 153)));
+        assert_eq!(parse("  #000000 "), Ok(Color::Rgb(0, 0, 0)));
+    }
+
+    #[test]
+    fn parses_rgb_colors() {
+        assert_eq!(parse("rgb(242, 60, 147)"), Ok(Color::Rgb(242, 60, 147)));
+        assert_eq!(parse("rgb(0,0,255)"), Ok(Color::Rgb(0, 0, 255)));
+    }
+
+    #[test]
+    fn parses_names_ignoring_case_dashes_and_underscores() {
+        assert_eq!(parse("light_red"), Ok(Color::LightRed));
+        assert_eq!(parse("LightRed"), Ok(Color::LightRed));
+        assert_eq!(parse("light-red"), Ok(Color::LightRed));
+        assert_eq!(parse("dark_gray"), Ok(Color::DarkGray));
+        assert_eq!(parse("reset"), Ok(Color::Reset));
+    }
+
+    #[test]
+    fn parses_palette_indices() {
+        assert_eq!(parse("0"), Ok(Color::Indexed(0)));
+        assert_eq!(parse("208"), Ok(Color::Indexed(208)));
+        assert_eq!(parse("255"), Ok(Color::Indexed(255)));
+    }
+
+    #[test]
+    fn rejects_invalid_colors() {
+        for text in ["", "#", "#f23c9", "#f23c9312", "#ggg", "rgb(1, 2)", "rgb(1, 2, 256)", "rgb(a, b, c)", "256", "pink"] {
+            let error = parse(text).expect_err(text);
+            assert!(error.starts_with("invalid color"), "{}", error);
+        }
+    }
+
+    #[test]
+    fn formatted_colors_parse_back() {
+        let named = COLOR_NAMES.iter().map(|(_, color)| *color);
+        let others = [Color::Rgb(242, 60, 147), Color::Rgb(0, 0, 0), Color::Indexed(0), Color::Indexed(255)];
+        for color in named.chain(others) {
+            assert_eq!(parse(&format(color)), Ok(color), "{}", format(color));
+        }
+        assert_eq!(format(Color::Rgb(242, 60, 147)), "#f23c93");
+        assert_eq!(format(Color::LightRed), "light_red");
+    }
+
+    #[test]
+    fn reads_optional_colors_in_every_form() {
+        #[derive(Deserialize, Serialize)]
+        struct Colors {
+            #[serde(with = "optional")]
+            color: Option<Color>,
+        }
+        let read = |json: &str| serde_json::from_str::<Colors>(json).map(|colors| colors.color);
+
+        assert_eq!(read(r##"{"color": "#f23c93"}"##).ok(), Some(Some(Color::Rgb(242, 60, 147))));
+        assert_eq!(read(r#"{"color": 208}"#).ok(), Some(Some(Color::Indexed(208))));
+        assert_eq!(read(r#"{"color": {"Rgb": [242, 60, 147]}}"#).ok(), Some(Some(Color::Rgb(242, 60, 147))));
+        assert_eq!(read(r#"{"color": "Magenta"}"#).ok(), Some(Some(Color::Magenta)));
+        assert_eq!(read(r#"{"color": null}"#).ok(), Some(None));
+        assert!(read(r#"{"color": "pink"}"#).is_err());
+
+        let json = serde_json::to_string(&Colors { color: Some(Color::Rgb(242, 60, 147)) }).unwrap();
+        assert_eq!(json, r##"{"color":"#f23c93"}"##);
+    }
+
+    #[test]
+    fn downgrades_to_what_the_terminal_supports() {
+        let pink = Color::Rgb(242, 60, 147);
+        assert_eq!(downgrade(pink, ColorSupport::Truecolor), pink);
+        assert!(matches!(downgrade(pink, ColorSupport::Ansi256), Color::Indexed(16..=255)));
+        assert_eq!(downgrade(Color::Rgb(250, 0, 0), ColorSupport::Ansi16), Color::LightRed);
+        assert_eq!(downgrade(Color::Indexed(196), ColorSupport::Ansi16), Color::LightRed);
+        assert_eq!(downgrade(pink, ColorSupport::None), Color::Reset);
+    }
+}
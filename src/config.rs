@@ -1,68 +1,557 @@
+use crate::{color, keys::Keys, theme::Theme};
 use crossterm::event::KeyCode;
-use serde::{Deserialize, Serialize};
+use serde::{de::{self, DeserializeOwned}, Deserialize, Serialize};
 use tui::style::Color;
 
 /// Configuration structure for the application, storing color settings and key bindings.
 #[derive(Serialize, Deserialize, Default, Debug)]
+#[serde(default)]
 pub struct Config {
     pub colors: Colors,         // Color settings for the UI.
     pub keybindings: KeyBindings, // Key bindings for application actions.
+    pub settings: Settings,     // General behaviour settings.
+    pub glyphs: Glyphs,         // Symbols and borders the UI is drawn with.
+    pub layout: LayoutSettings, // Placement of the sections of the UI.
 }
 
-/// Defines color settings used throughout the application.
+/// General settings controlling how the application behaves.
 #[derive(Serialize, Deserialize, Debug)]
-pub struct Colors {
-    pub foreground: Color,       // Default foreground color.
-    pub background: Color,       // Default background color.
-    pub selection_fg: Color,     // Foreground color for selected items.
-    pub selection_bg: Color,     // Background color for selected items.
-    pub check_sign: Color,       // Color for check marks in tasks.
-    pub welcome_message: Color,       // Color for welcome message.
+#[serde(default)]
+pub struct Settings {
+    pub insert_position: InsertPosition, // Where newly added tasks are placed in the list.
+    pub history_limit: usize,            // Number of mutations that can be undone.
+    pub saved_history_limit: usize,      // Number of undo steps kept across restarts, 0 to disable.
+    pub trash_retention_days: u64,       // Days a deleted task stays in the trash, 0 to keep forever.
+    pub auto_archive_days: u64,          // Days after completion a task is archived at startup, 0 to disable.
+    pub keymap: KeymapPreset,            // Built-in key bindings that `keybindings` overrides.
+    pub mouse: bool,                     // Whether clicks and the mouse wheel are captured by the app.
+    pub confirm: ConfirmSettings,        // Which actions ask for confirmation first.
+    pub message_seconds: u64,            // Seconds a message stays in the status line, 0 to keep it.
+    pub theme: String,                   // Name of a built-in theme or of a theme file in the themes directory.
+    pub color_support: ColorSupport,     // Colors the terminal can show, detected from the environment by default.
+    pub ascii: bool,                     // Whether `glyphs` starts from plain ASCII symbols instead of Unicode ones.
+    pub stats_weeks: usize,              // Weeks of daily completions charted on the statistics screen.
+}
+
+/// Provides default settings.
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            insert_position: InsertPosition::default(),
+            history_limit: 100,
+            saved_history_limit: 20,
+            trash_retention_days: 30,
+            auto_archive_days: 0,
+            keymap: KeymapPreset::default(),
+            mouse: false,
+            confirm: ConfirmSettings::default(),
+            message_seconds: 5,
+            theme: "neon".to_string(),
+            color_support: ColorSupport::default(),
+            ascii: false,
+            stats_weeks: 4,
+        }
+    }
+}
+
+/// Chooses which actions ask "are you sure?" before they are performed.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(default)]
+pub struct ConfirmSettings {
+    pub quit: bool,            // Confirm before exiting the application.
+    pub delete: bool,          // Confirm before deleting tasks, purging them or emptying the trash.
+    pub clear_completed: bool, // Confirm before archiving all completed tasks.
+    pub bulk: bool,            // Confirm before changing several marked tasks at once.
 }
 
-/// Provides default color values, useful for initial configuration or resets.
-impl Default for Colors {
+/// Provides default confirmation settings: only quitting is confirmed.
+impl Default for ConfirmSettings {
     fn default() -> Self {
         Self {
-            foreground: Color::Rgb(242, 60, 147),
-            background: Color::Rgb(0, 0, 0),
-            selection_fg: Color::Rgb(255, 255, 255),
-            selection_bg: Color::Rgb(0, 128, 128),
-            check_sign: Color::Rgb(217, 200, 25),
-            welcome_message: Color::Rgb(242, 60, 147),
+            quit: true,
+            delete: false,
+            clear_completed: false,
+            bulk: false,
+        }
+    }
+}
+
+/// Where a task saved in adding mode is inserted into the task list.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InsertPosition {
+    /// Append new tasks to the end of the list.
+    #[default]
+    Bottom,
+    /// Insert new tasks at the start of the list.
+    Top,
+    /// Insert new tasks directly above the selected task.
+    AboveSelection,
+    /// Insert new tasks directly below the selected task.
+    BelowSelection,
+}
+
+/// Built-in sets of key bindings. Bindings in the config file override individual keys of the chosen preset.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum KeymapPreset {
+    /// Arrow keys and single letters.
+    #[default]
+    Default,
+    /// Vim-style motions, with counts such as `5j` and `3dd`.
+    Vim,
+    /// Emacs-style control key bindings, with `C-g` to cancel anything.
+    Emacs,
+}
+
+/// How many colors the terminal can show. Theme colors the terminal cannot show are replaced by the nearest
+/// color it can.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorSupport {
+    /// Detect from the `NO_COLOR`, `COLORTERM` and `TERM` environment variables.
+    #[default]
+    Auto,
+    /// Any RGB color.
+    Truecolor,
+    /// The 256-color xterm palette.
+    Ansi256,
+    /// The 16 basic ANSI colors.
+    Ansi16,
+    /// No colors at all, only attributes such as bold and reverse.
+    None,
+}
+
+/// Where the sections of the UI go and which of them are shown.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(default)]
+pub struct LayoutSettings {
+    pub input_position: InputPosition,   // Where the input field is placed.
+    pub margin: u16,                     // Empty rows and columns around the UI, dropped on small terminals.
+    pub show_hint: bool,                 // Whether the navigation hint is shown below the list.
+    pub detail_position: DetailPosition, // Where the details of the selected task are shown.
+    pub progress_format: String,         // Counts shown in the title of the task list, empty to show none.
+    pub progress_gauge: bool,            // Whether a completion gauge is drawn on the bottom border of the list.
+}
+
+/// Provides the default layout: input field on top, a margin of 2, the navigation hint shown and the completed
+/// task count in the title of the task list.
+impl Default for LayoutSettings {
+    fn default() -> Self {
+        Self {
+            input_position: InputPosition::default(),
+            margin: 2,
+            show_hint: true,
+            detail_position: DetailPosition::default(),
+            progress_format: "{done}/{total} done".to_string(),
+            progress_gauge: false,
+        }
+    }
+}
+
+/// Where the input field for adding tasks, tags and searches is placed.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum InputPosition {
+    /// Above the list.
+    #[default]
+    Top,
+    /// Below the list.
+    Bottom,
+    /// In a popup that only appears while text is typed into it, giving the list the full height.
+    Popup,
+}
+
+/// Where the pane with the details of the selected task goes.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum DetailPosition {
+    /// To the right of the list.
+    #[default]
+    Side,
+    /// Below the list.
+    Bottom,
+}
+
+/// Symbols the task list and other widgets are drawn with.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct Glyphs {
+    pub check: String,           // Check column of a completed task.
+    pub uncheck: String,         // Check column of a task that is not completed.
+    pub marker: String,          // First column of a marked task.
+    pub priority_low: String,    // In front of the title of a low priority task.
+    pub priority_medium: String, // In front of the title of a medium priority task.
+    pub priority_high: String,   // In front of the title of a high priority task.
+    pub tree_middle: String,     // Check column of a wrapped row of a title that continues on the next row.
+    pub tree_last: String,       // Check column of the last wrapped row of a title.
+    pub ellipsis: String,        // After text that continues, such as a partly typed key sequence.
+    pub border: BorderStyle,     // Lines drawn around each section of the UI.
+}
+
+/// Provides the Unicode glyphs.
+impl Default for Glyphs {
+    fn default() -> Self {
+        Self {
+            check: "✔".to_string(),
+            uncheck: " ".to_string(),
+            marker: "●".to_string(),
+            priority_low: "!".to_string(),
+            priority_medium: "!!".to_string(),
+            priority_high: "!!!".to_string(),
+            tree_middle: "│".to_string(),
+            tree_last: "└".to_string(),
+            ellipsis: "…".to_string(),
+            border: BorderStyle::Rounded,
+        }
+    }
+}
+
+impl Glyphs {
+    /// Returns the Unicode glyphs, or ones made only of ASCII characters for terminals and fonts without Unicode
+    /// support.
+    pub fn preset(ascii: bool) -> Self {
+        if !ascii {
+            return Self::default();
+        }
+
+        Self {
+            check: "x".to_string(),
+            marker: "*".to_string(),
+            tree_middle: "|".to_string(),
+            tree_last: "`".to_string(),
+            ellipsis: "...".to_string(),
+            border: BorderStyle::Ascii,
+            ..Self::default()
+        }
+    }
+}
+
+/// Lines drawn around the sections of the UI.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum BorderStyle {
+    /// Single lines with rounded corners.
+    #[default]
+    Rounded,
+    /// Single lines with square corners.
+    Plain,
+    /// Double lines.
+    Double,
+    /// Heavy lines.
+    Thick,
+    /// `-`, `|` and `+`, for terminals without box-drawing characters.
+    Ascii,
+}
+
+impl Config {
+    /// Parses a config file. The key bindings start from the preset named in `settings.keymap`, and any
+    /// bindings given in the file replace the preset's keys for those actions. The glyphs start from the ASCII
+    /// or Unicode preset chosen by `settings.ascii` in the same way.
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        let mut value: serde_json::Value = serde_json::from_str(json)?;
+
+        let settings = match value.get("settings") {
+            Some(settings) => deserialize_at("settings", settings.clone())?,
+            None => Settings::default(),
+        };
+
+        merge_preset(&mut value, "keybindings", serde_json::to_value(KeyBindings::preset(settings.keymap))?);
+        merge_preset(&mut value, "glyphs", serde_json::to_value(Glyphs::preset(settings.ascii))?);
+
+        deserialize_at("", value)
+    }
+}
+
+/// Replaces the `section` of the config with `preset`, keeping the entries the config gives for that section.
+fn merge_preset(config: &mut serde_json::Value, section: &str, mut preset: serde_json::Value) {
+    if let (Some(preset), Some(overrides)) = (
+        preset.as_object_mut(),
+        config.get(section).and_then(serde_json::Value::as_object),
+    ) {
+        for (name, value) in overrides {
+            preset.insert(name.clone(), value.clone());
+        }
+    }
+
+    if let Some(config) = config.as_object_mut() {
+        config.insert(section.to_string(), preset);
+    }
+}
+
+/// Deserializes `value`, naming the field that holds an invalid value in the error, e.g.
+/// `colors.foreground: invalid color "#f23c9"`. `prefix` is the path of `value` within the config file.
+fn deserialize_at<T: DeserializeOwned>(prefix: &str, value: serde_json::Value) -> Result<T, serde_json::Error> {
+    serde_path_to_error::deserialize(value).map_err(|error| {
+        let path = match (prefix, error.path().to_string().as_str()) {
+            (prefix, ".") => prefix.to_string(),
+            ("", path) => path.to_string(),
+            (prefix, path) => format!("{}.{}", prefix, path),
+        };
+        let inner = error.into_inner();
+        if path.is_empty() {
+            inner
+        } else {
+            de::Error::custom(format!("{}: {}", path, inner))
+        }
+    })
+}
+
+/// Individual colors that override the active theme, kept so that configs written before themes existed still
+/// apply. Colors left out come from the theme.
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct Colors {
+    #[serde(with = "color::optional", skip_serializing_if = "Option::is_none")]
+    pub foreground: Option<Color>,      // Default foreground color.
+    #[serde(with = "color::optional", skip_serializing_if = "Option::is_none")]
+    pub background: Option<Color>,      // Default background color.
+    #[serde(with = "color::optional", skip_serializing_if = "Option::is_none")]
+    pub selection_fg: Option<Color>,    // Foreground color for selected items.
+    #[serde(with = "color::optional", skip_serializing_if = "Option::is_none")]
+    pub selection_bg: Option<Color>,    // Background color for selected items.
+    #[serde(with = "color::optional", skip_serializing_if = "Option::is_none")]
+    pub check_sign: Option<Color>,      // Color for check marks in tasks.
+    #[serde(with = "color::optional", skip_serializing_if = "Option::is_none")]
+    pub welcome_message: Option<Color>, // Color for welcome message.
+    #[serde(with = "color::optional", skip_serializing_if = "Option::is_none")]
+    pub info: Option<Color>,            // Color for feedback in the status line.
+    #[serde(with = "color::optional", skip_serializing_if = "Option::is_none")]
+    pub warning: Option<Color>,         // Color for warnings in the status line.
+    #[serde(with = "color::optional", skip_serializing_if = "Option::is_none")]
+    pub error: Option<Color>,           // Color for errors in the status line.
+}
+
+impl Colors {
+    /// Replaces the colors of `theme` that are set here.
+    pub fn apply(&self, theme: &mut Theme) {
+        let overrides = [
+            (self.foreground, &mut theme.text.fg),
+            (self.background, &mut theme.text.bg),
+            (self.selection_fg, &mut theme.selection.fg),
+            (self.selection_bg, &mut theme.selection.bg),
+            (self.check_sign, &mut theme.check_sign.fg),
+            (self.info, &mut theme.info.fg),
+            (self.warning, &mut theme.warning.fg),
+            (self.error, &mut theme.error.fg),
+        ];
+
+        for (color, target) in overrides {
+            if color.is_some() {
+                *target = color;
+            }
         }
     }
 }
 
-/// Struct for mapping application actions to keyboard keys.
+/// Struct for mapping application actions to keyboard keys. Each action accepts several keys, which may
+/// include modifiers or be sequences typed one after the other.
 #[derive(Serialize, Deserialize, Debug)]
+#[serde(default)]
 pub struct KeyBindings {
-    pub list_up: KeyCode,             // Key to navigate up in the task list.
-    pub list_down: KeyCode,           // Key to navigate down in the task list.
-    pub check_and_uncheck_task: KeyCode, // Key to mark a task as complete or incomplete.
-    pub exit_app: KeyCode,            // Key to exit the application.
-    pub new_task: KeyCode,            // Key to initiate adding a new task.
-    pub delete_task: KeyCode,         // Key to delete a task.
-    pub exit_adding_mode: KeyCode,         // Key to exit the task adding mode.
-    pub save_task: KeyCode,           // Key to save a new task.
+    pub list_up: Keys,                // Keys to navigate up in the task list.
+    pub list_down: Keys,              // Keys to navigate down in the task list.
+    pub list_top: Keys,               // Keys to jump to the first task, or to task N with a count.
+    pub list_bottom: Keys,            // Keys to jump to the last task, or to task N with a count.
+    pub check_and_uncheck_task: Keys, // Keys to mark a task as complete or incomplete.
+    pub exit_app: Keys,               // Keys to exit the application.
+    pub new_task: Keys,               // Keys to initiate adding a new task.
+    pub new_task_above: Keys,         // Keys to add new tasks directly above the selected task.
+    pub new_task_below: Keys,         // Keys to add new tasks directly below the selected task.
+    pub delete_task: Keys,            // Keys to delete a task.
+    pub move_task_up: Keys,           // Keys to move the selected task one position up.
+    pub move_task_down: Keys,         // Keys to move the selected task one position down.
+    pub move_task_top: Keys,          // Keys to move the selected task to the top of the list.
+    pub move_task_bottom: Keys,       // Keys to move the selected task to the bottom of the list.
+    pub edit_task: Keys,              // Keys to edit the title of the selected task.
+    pub undo: Keys,                   // Keys to undo the last change to the task list.
+    pub redo: Keys,                   // Keys to redo the last undone change.
+    pub view_trash: Keys,             // Keys to open the trash view.
+    pub restore_task: Keys,           // Keys to restore the selected task from the trash.
+    pub purge_task: Keys,             // Keys to permanently delete the selected task from the trash.
+    pub empty_trash: Keys,            // Keys to permanently delete everything in the trash.
+    pub archive_completed: Keys,      // Keys to move all completed tasks to the archive.
+    pub view_archive: Keys,           // Keys to open the archive view.
+    pub search: Keys,                 // Keys to start searching the task list or the archive.
+    pub search_next: Keys,            // Keys to select the next task matching the search.
+    pub search_previous: Keys,        // Keys to select the previous task matching the search.
+    pub exit_view: Keys,              // Keys to leave the trash, archive or statistics view.
+    pub toggle_mark: Keys,            // Keys to mark or unmark the selected task for a batch operation.
+    pub visual_mode: Keys,            // Keys to start marking a range of tasks.
+    pub clear_marks: Keys,            // Keys to unmark every task.
+    pub cycle_priority: Keys,         // Keys to cycle the priority of the selected or marked tasks.
+    pub tag_task: Keys,               // Keys to add or remove a tag on the selected or marked tasks.
+    pub exit_adding_mode: Keys,       // Keys to exit the task adding mode.
+    pub save_task: Keys,              // Keys to save a new task.
+    pub input_cursor_left: Keys,      // Keys to move the input cursor one character left.
+    pub input_cursor_right: Keys,     // Keys to move the input cursor one character right.
+    pub input_start: Keys,            // Keys to move the input cursor to the start of the text.
+    pub input_end: Keys,              // Keys to move the input cursor to the end of the text.
+    pub input_kill_line: Keys,        // Keys to delete from the input cursor to the end of the text.
+    pub cancel: Keys,                 // Keys to leave any mode and return to the task list.
+    pub save_to_disk: Keys,           // Keys to write the tasks to disk without exiting.
+    pub help: Keys,                   // Keys to open and close the list of key bindings.
+    pub confirm_yes: Keys,            // Keys to answer yes in a confirmation dialog.
+    pub confirm_no: Keys,             // Keys to answer no in a confirmation dialog.
+    pub message_history: Keys,        // Keys to open and close the history of status line messages.
+    pub cycle_theme: Keys,            // Keys to switch to the next theme.
+    pub view_stats: Keys,             // Keys to switch between the task list and the statistics screen.
+    pub toggle_details: Keys,         // Keys to show or hide the details of the selected task.
+    pub scroll_details_up: Keys,      // Keys to scroll the task details up.
+    pub scroll_details_down: Keys,    // Keys to scroll the task details down.
+    pub command_palette: Keys,        // Keys to open the command palette.
+    pub previous_command: Keys,       // Keys to select the previous command in the command palette.
+    pub next_command: Keys,           // Keys to select the next command in the command palette.
 }
 
 /// Provides default key bindings for actions.
 impl Default for KeyBindings {
     fn default() -> Self {
         Self {
-            list_up: KeyCode::Up,
-            list_down: KeyCode::Down,
-            check_and_uncheck_task: KeyCode::Enter,
-            exit_app: KeyCode::Esc,
-            new_task: KeyCode::Char('a'),
-            delete_task: KeyCode::Delete,
-            exit_adding_mode: KeyCode::Esc,
-            save_task: KeyCode::Enter,
+            list_up: keys(&["Up"]),
+            list_down: keys(&["Down"]),
+            list_top: keys(&["Home"]),
+            list_bottom: keys(&["End"]),
+            check_and_uncheck_task: keys(&["Enter"]),
+            exit_app: keys(&["Esc"]),
+            new_task: keys(&["a"]),
+            new_task_above: keys(&[]),
+            new_task_below: keys(&[]),
+            delete_task: keys(&["Delete"]),
+            move_task_up: keys(&["K"]),
+            move_task_down: keys(&["J"]),
+            move_task_top: keys(&["T"]),
+            move_task_bottom: keys(&["B"]),
+            edit_task: keys(&["e"]),
+            undo: keys(&["u"]),
+            redo: keys(&["r"]),
+            view_trash: keys(&["t"]),
+            restore_task: keys(&["Enter"]),
+            purge_task: keys(&["Delete"]),
+            empty_trash: keys(&["E"]),
+            archive_completed: keys(&["A"]),
+            view_archive: keys(&["v"]),
+            search: keys(&["/"]),
+            search_next: keys(&["n"]),
+            search_previous: keys(&["N"]),
+            exit_view: keys(&["Esc"]),
+            toggle_mark: keys(&["Space"]),
+            visual_mode: keys(&["V"]),
+            clear_marks: keys(&["c"]),
+            cycle_priority: keys(&["p"]),
+            tag_task: keys(&["#"]),
+            exit_adding_mode: keys(&["Esc"]),
+            save_task: keys(&["Enter"]),
+            input_cursor_left: keys(&["Left"]),
+            input_cursor_right: keys(&["Right"]),
+            input_start: keys(&["Home"]),
+            input_end: keys(&["End"]),
+            input_kill_line: keys(&[]),
+            cancel: keys(&[]),
+            save_to_disk: keys(&["C-s"]),
+            help: keys(&["?", "F1"]),
+            confirm_yes: keys(&["Enter", "y"]),
+            confirm_no: keys(&["n", "Esc"]),
+            message_history: keys(&["M"]),
+            cycle_theme: keys(&["C-t"]),
+            view_stats: keys(&["S"]),
+            toggle_details: keys(&["D"]),
+            scroll_details_up: keys(&["PageUp"]),
+            scroll_details_down: keys(&["PageDown"]),
+            command_palette: keys(&[":", "C-p"]),
+            previous_command: keys(&["Up", "C-p"]),
+            next_command: keys(&["Down", "C-n"]),
         }
     }
 }
 
+impl KeyBindings {
+    /// Returns the key bindings of a built-in preset.
+    pub fn preset(preset: KeymapPreset) -> Self {
+        match preset {
+            KeymapPreset::Default => Self::default(),
+            KeymapPreset::Vim => Self::vim(),
+            KeymapPreset::Emacs => Self::emacs(),
+        }
+    }
+
+    /// Vim-style bindings. Counts typed before a key repeat it, e.g. `5j` or `3dd`.
+    fn vim() -> Self {
+        Self {
+            list_up: keys(&["k", "Up"]),
+            list_down: keys(&["j", "Down"]),
+            list_top: keys(&["g g", "Home"]),
+            list_bottom: keys(&["G", "End"]),
+            check_and_uncheck_task: keys(&["x", "Enter"]),
+            exit_app: keys(&["q", "Z Z"]),
+            new_task: keys(&["a", "i"]),
+            new_task_above: keys(&["O"]),
+            new_task_below: keys(&["o"]),
+            delete_task: keys(&["d d", "Delete"]),
+            edit_task: keys(&["e"]),
+            undo: keys(&["u"]),
+            redo: keys(&["C-r"]),
+            view_trash: keys(&["g t"]),
+            view_archive: keys(&["g a"]),
+            visual_mode: keys(&["V", "v"]),
+            restore_task: keys(&["p", "Enter"]),
+            scroll_details_up: keys(&["C-u", "PageUp"]),
+            scroll_details_down: keys(&["C-d", "PageDown"]),
+            purge_task: keys(&["d d", "Delete"]),
+            exit_view: keys(&["Esc", "q"]),
+            // Actions without a vim counterpart keep their default keys, listed here so the preset reads in full.
+            move_task_up: keys(&["K"]),
+            move_task_down: keys(&["J"]),
+            move_task_top: keys(&["T"]),
+            move_task_bottom: keys(&["B"]),
+            clear_marks: keys(&["c"]),
+            cycle_priority: keys(&["p"]),
+            tag_task: keys(&["#"]),
+            empty_trash: keys(&["E"]),
+            archive_completed: keys(&["A"]),
+            ..Self::default()
+        }
+    }
+}
+
+impl KeyBindings {
+    /// Emacs-style bindings, including line editing keys in the input field.
+    fn emacs() -> Self {
+        Self {
+            list_up: keys(&["C-p", "Up"]),
+            list_down: keys(&["C-n", "Down"]),
+            list_top: keys(&["M-<", "Home"]),
+            list_bottom: keys(&["M->", "End"]),
+            check_and_uncheck_task: keys(&["C-c C-c", "Enter"]),
+            exit_app: keys(&["C-x C-c", "Esc"]),
+            new_task_below: keys(&["C-o"]),
+            delete_task: keys(&["C-d", "Delete"]),
+            // Terminals send `C-/` and `C-_` as the same control byte, which is read as `C-7`.
+            undo: keys(&["C-7", "C-x u"]),
+            redo: keys(&["M-_"]),
+            search: keys(&["C-s", "/"]),
+            search_next: keys(&["M-n", "n"]),
+            search_previous: keys(&["M-p", "N"]),
+            toggle_mark: keys(&["C-Space", "Space"]),
+            input_cursor_left: keys(&["C-b", "Left"]),
+            input_cursor_right: keys(&["C-f", "Right"]),
+            input_start: keys(&["C-a", "Home"]),
+            input_end: keys(&["C-e", "End"]),
+            input_kill_line: keys(&["C-k"]),
+            cancel: keys(&["C-g"]),
+            save_to_disk: keys(&["C-x C-s"]),
+            command_palette: keys(&["M-x", ":"]),
+            scroll_details_up: keys(&["M-v", "PageUp"]),
+            scroll_details_down: keys(&["C-v", "PageDown"]),
+            ..Self::default()
+        }
+    }
+}
+
+/// Parses the key bindings of a built-in keymap.
+fn keys(specs: &[&str]) -> Keys {
+    Keys::parse(specs).expect("built-in key bindings are valid")
+}
+
 /// Converts a `KeyCode` into a string.
 pub fn keycode_to_string(keycode: KeyCode) -> String {
     let temp;
@@ -88,6 +577,7 @@ pub fn keycode_to_string(keycode: KeyCode) -> String {
             temp = format!("F{}", n);
             temp.as_str()
         },
+        KeyCode::Char(' ') => "Space",
         KeyCode::Char(char) => {
             temp = char.to_string();
             temp.as_str()
@@ -96,4 +586,38 @@ pub fn keycode_to_string(keycode: KeyCode) -> String {
     .to_string();
 
     stringified
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_config_round_trips() {
+        let json = serde_json::to_string_pretty(&Config::default()).unwrap();
+        let config = Config::from_json(&json).unwrap();
+        assert_eq!(serde_json::to_string_pretty(&config).unwrap(), json);
+    }
+
+    #[test]
+    fn colors_round_trip_in_every_form() {
+        let json = r##"{"colors": {"foreground": "#f39", "background": "rgb(0, 0, 32)", "selection_fg": "light_red",
+            "selection_bg": 236, "check_sign": {"Rgb": [0, 255, 0]}}}"##;
+        let config = Config::from_json(json).unwrap();
+        let colors = serde_json::to_value(&config.colors).unwrap();
+        assert_eq!(
+            colors,
+            serde_json::json!({"foreground": "#ff3399", "background": "#000020", "selection_fg": "light_red",
+                "selection_bg": "236", "check_sign": "#00ff00"})
+        );
+
+        let reread = Config::from_json(&serde_json::to_string(&config).unwrap()).unwrap();
+        assert_eq!(serde_json::to_value(&reread.colors).unwrap(), colors);
+    }
+
+    #[test]
+    fn invalid_color_names_the_field() {
+        let error = Config::from_json(r##"{"colors": {"foreground": "#f23c9"}}"##).unwrap_err();
+        assert!(error.to_string().starts_with("colors.foreground: invalid color \"#f23c9\""), "{}", error);
+    }
+}
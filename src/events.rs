@@ -1,80 +1,125 @@
 ///----------events.rs----------///
 
 
-use crate::{utils, App, InputMode, TerminalFrame};
-use crossterm::event::{KeyCode, KeyEvent};
+use crate::{
+    action::{dispatch, dispatch_counted, Action},
+    keymap::KeyMatch,
+    keys::KeyPress,
+    utils,
+    widget::{self, MainLayout},
+    App, InputMode, TerminalFrame,
+};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use unicode_width::UnicodeWidthStr;
 use tui::layout::Rect;
 
-/// Input events handler
+/// Largest repeat count that can be typed before an action.
+const MAX_COUNT: usize = 9999;
+
+/// Input events handler. Resolves the key through the keymap of the current mode and dispatches the action.
 pub fn handle_events(event: KeyEvent, app: &mut App) {
-    match app.input_mode {
-        InputMode::Normal => handle_normal_events(app, event.code),
-        InputMode::Adding => handle_adding_events(app, event.code),
-        InputMode::ConfirmQuit => handle_confirm_quit_events(app, event.code),
+    let press = KeyPress::from(event);
+
+    if let Some(digit) = count_digit(app, press) {
+        app.count = Some((app.count.unwrap_or(0) * 10 + digit).min(MAX_COUNT));
+        return;
     }
-}
 
-/// When user is viewing tasks
-fn handle_normal_events(app: &mut App, keycode: KeyCode) {
-    let keybindings = &app.config.keybindings;
-
-    if keycode == keybindings.new_task {
-        utils::new_task(app);
-    } else if keycode == keybindings.exit_app {
-        utils::exit_app(app);
-    } else if keycode == keybindings.list_up {
-        utils::list_up(app);
-    } else if keycode == keybindings.list_down {
-        utils::list_down(app);
-    } else if keycode == keybindings.check_and_uncheck_task {
-        utils::check_and_uncheck_task(app);
-    } else if keycode == keybindings.delete_task {
-        utils::delete_task(app);
+    match app.keymap.resolve(app.input_mode, &mut app.pending_keys, press) {
+        KeyMatch::Matched(action) => {
+            let count = app.count.take();
+            dispatch_counted(app, action, count);
+        }
+        KeyMatch::Pending => {}
+        KeyMatch::Unmatched => app.count = None,
     }
 }
 
-/// When user adding a new task
-fn handle_adding_events(app: &mut App, keycode: KeyCode) {
-    let keybindings = &app.config.keybindings;
-
-    if keycode == keybindings.save_task && !app.input.trim().is_empty() {
-        utils::save_task(app);
-    } else if keycode == keybindings.exit_adding_mode {
-        utils::exit_adding_mode(app);
-    } else if let KeyCode::Char(c) = keycode {
-        utils::input_add_char(app, c);
-    } else if let KeyCode::Backspace = keycode {
-        utils::input_del_char(app);
+/// Mouse events handler. Clicking a task selects it and clicking its check column checks or unchecks it, clicking
+/// the input field starts adding a task, and the wheel moves the selection of whatever list is shown.
+/// `layout` holds the sections of the screen from `widget::main_chunks`.
+pub fn handle_mouse(event: MouseEvent, app: &mut App, layout: &MainLayout) {
+    let (column, row) = (event.column, event.row);
+
+    match event.kind {
+        MouseEventKind::ScrollUp | MouseEventKind::ScrollDown => {
+            let up = event.kind == MouseEventKind::ScrollUp;
+            let action = match app.input_mode {
+                InputMode::Palette if up => Action::PreviousCommand,
+                InputMode::Palette => Action::NextCommand,
+                mode if mode.accepts_text() || matches!(mode, InputMode::Confirm | InputMode::Stats) => return,
+                _ if up => Action::ListUp,
+                _ => Action::ListDown,
+            };
+            dispatch(app, action);
+        }
+        MouseEventKind::Down(MouseButton::Left) if app.input_mode == InputMode::Normal => {
+            if layout.input.is_some_and(|input| contains(input, column, row)) {
+                dispatch(app, Action::NewTask);
+                return;
+            }
+
+            // Rows inside the border of the list map to tasks, counting the rows of each task from the top one.
+            let list = layout.list;
+            if !contains(list, column, row) || row == list.y || row + 1 >= list.y + list.height {
+                return;
+            }
+            let heights = widget::task_heights(app, list.width);
+            let mut top = list.y + 1;
+            let mut index = app.task_offset;
+            while index < heights.len() && top + heights[index] as u16 <= row {
+                top += heights[index] as u16;
+                index += 1;
+            }
+            let check_column = list.x + 1 + widget::marker_width(app);
+
+            // Only the first row of a wrapped task has a check column.
+            if row == top && (check_column..check_column + widget::check_width(app)).contains(&column) {
+                utils::toggle_task(app, index);
+            } else {
+                utils::select_task(app, index);
+            }
+        }
+        _ => {}
     }
 }
 
-fn handle_confirm_quit_events(app: &mut App, keycode: KeyCode) {
-    match keycode {
-        KeyCode::Enter => app.should_exit = true,  // Confirm and exit
-        KeyCode::Char('n') => {
-            app.input_mode = InputMode::Normal;  // Cancel and return to normal mode
-        },
-        _ => {}  // Ignore other keys in this mode
+/// Returns whether the cell at `column` and `row` lies within `area`.
+fn contains(area: Rect, column: u16, row: u16) -> bool {
+    (area.x..area.x + area.width).contains(&column) && (area.y..area.y + area.height).contains(&row)
+}
+
+/// Returns the digit if `press` continues a repeat count: an unbound digit typed in the task list before the
+/// keys of an action. A count cannot start with 0.
+fn count_digit(app: &App, press: KeyPress) -> Option<usize> {
+    let digit = match press.code {
+        KeyCode::Char(c) if press.modifiers == KeyModifiers::NONE => c.to_digit(10)? as usize,
+        _ => return None,
+    };
+
+    let counting_mode = matches!(app.input_mode, InputMode::Normal | InputMode::Visual);
+    let starts_count = digit != 0 || app.count.is_some();
+
+    if counting_mode && starts_count && app.pending_keys.is_empty() && !app.keymap.is_bound(app.input_mode, press) {
+        Some(digit)
+    } else {
+        None
     }
 }
 
-/// Handle cursor when typing
-pub fn handle_input_cursor(app: &App, frame: &mut TerminalFrame, chunks: &[Rect]) {
-    match app.input_mode {
-        InputMode::Normal => {
-            // No need to handle cursor in normal mode
-        },
-        InputMode::Adding => {
-            // Make the cursor visible and ask tui-rs to put it at the specified coordinates after rendering
-            frame.set_cursor(
-                // Put cursor past the end of the input text
-                chunks[0].x + app.input.len() as u16 + 1,
-                // Move one line down, from the border to the input line
-                chunks[0].y + 1,
-            )
-        },
-        InputMode::ConfirmQuit => {
-            // No need to handle cursor in confirm quit mode
-        }
+/// Handle cursor when typing into the input field drawn in `area`
+pub fn handle_input_cursor(app: &App, frame: &mut TerminalFrame, area: Rect) {
+    // Only modes with an input field show the cursor
+    if let Some(input) = app.active_input() {
+        // Wide characters take up two columns, so the cursor goes after the display width of the text before it.
+        let before: String = input.chars().take(app.cursor).collect();
+
+        // Make the cursor visible and ask tui-rs to put it at the specified coordinates after rendering
+        frame.set_cursor(
+            // Put cursor after the characters before it, past the border
+            area.x + before.width() as u16 + 1,
+            // Move one line down, from the border to the input line
+            area.y + 1,
+        )
     }
-}
\ No newline at end of file
+}
@@ -1,8 +1,9 @@
 ///----------file-handlers.rs----------///
 
-use crate::{DynResult, Task, TaskList};
+use crate::{config::Config, history::History, keymap::Keymap, theme::Theme, App, Archive, ArchivedTask, DynResult, Task, TaskList, Trash, TrashedTask};
 use directories_next::ProjectDirs;
 use std::{
+    error::Error,
     fs, io,
     path::{Path, PathBuf},
 };
@@ -14,14 +15,29 @@ fn get_project_paths() -> ProjPaths {
     fs::create_dir_all(project_dirs.config_dir()).expect("Failed to create config directory");
 
     let data_path = project_dirs.data_dir().join("data.json");
+    let trash_path = project_dirs.data_dir().join("trash.json");
+    let archive_path = project_dirs.data_dir().join("archive.json");
+    let history_path = project_dirs.data_dir().join("history.json");
+    let config_path = project_dirs.config_dir().join("config.json");
+    let themes_path = project_dirs.config_dir().join("themes");
 
     ProjPaths {
         data_path,
+        trash_path,
+        archive_path,
+        history_path,
+        config_path,
+        themes_path,
     }
 }
 
 struct ProjPaths {
     data_path: PathBuf,  // Path to the data file where tasks are stored.
+    trash_path: PathBuf,  // Path to the file where deleted tasks are stored.
+    archive_path: PathBuf,  // Path to the file where archived tasks are stored.
+    history_path: PathBuf,  // Path to the file where the undo history is stored.
+    config_path: PathBuf,  // Path to the user's configuration file.
+    themes_path: PathBuf,  // Path to the directory of user theme files.
 }
 
 /// Load tasks from the stored JSON file. If the file does not exist, it creates a new empty file.
@@ -48,6 +64,161 @@ pub fn save_task(task: &[Task]) -> DynResult {
     Ok(())
 }
 
+/// Load the trashed tasks, dropping any that are older than `retention_days`. A missing or empty file gives an
+/// empty trash, while a file that cannot be read is an error rather than being replaced by an empty trash.
+pub fn load_trash(retention_days: u64) -> Result<Vec<TrashedTask>, Box<dyn Error>> {
+    let project_paths = get_project_paths();
+
+    if !Path::new(&project_paths.trash_path).exists() {
+        return Ok(Vec::new());
+    }
+
+    let stringified_trash = fs::read_to_string(&project_paths.trash_path)?;
+    if stringified_trash.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut trash: Trash = serde_json::from_str(&stringified_trash)
+        .map_err(|error| format!("Invalid trash file {}: {}", project_paths.trash_path.display(), error))?;
+    trash.purge_expired(retention_days);
+
+    Ok(trash.task)
+}
+
+/// Save the trashed tasks to their JSON file alongside the task data.
+pub fn save_trash(trash: &[TrashedTask]) -> DynResult {
+    let project_paths = get_project_paths();
+    let stringified_trash = serde_json::to_string(&Trash::new(trash))?;
+    fs::write(&project_paths.trash_path, stringified_trash)?;
+
+    Ok(())
+}
+
+/// Load the archived tasks. A missing file gives an empty archive.
+pub fn load_archive() -> Result<Vec<ArchivedTask>, io::Error> {
+    let project_paths = get_project_paths();
+
+    if !Path::new(&project_paths.archive_path).exists() {
+        return Ok(Vec::new());
+    }
+
+    let stringified_archive = fs::read_to_string(&project_paths.archive_path)?;
+    let archive: Archive = serde_json::from_str(&stringified_archive).unwrap_or_default();
+
+    Ok(archive.task)
+}
+
+/// Save the archived tasks to their JSON file alongside the task data.
+pub fn save_archive(archive: &[ArchivedTask]) -> DynResult {
+    let project_paths = get_project_paths();
+    let stringified_archive = serde_json::to_string(&Archive::new(archive))?;
+    fs::write(&project_paths.archive_path, stringified_archive)?;
+
+    Ok(())
+}
+
+/// Load the undo history saved by the previous session. A missing or unreadable file gives an empty history, and
+/// so does a `data.json` changed since the history was saved, whose edits undoing would otherwise overwrite.
+pub fn load_history() -> History {
+    let project_paths = get_project_paths();
+
+    let history: History = fs::read_to_string(&project_paths.history_path)
+        .ok()
+        .and_then(|stringified_history| serde_json::from_str(&stringified_history).ok())
+        .unwrap_or_default();
+    let data = fs::read_to_string(&project_paths.data_path).unwrap_or_default();
+
+    if history.data_fingerprint != Some(fingerprint(&data)) {
+        return History::default();
+    }
+    history
+}
+
+/// Save the most recent `limit` undo and redo steps so they survive a restart, together with a fingerprint of
+/// `data.json` as it is now. Save the tasks first.
+pub fn save_history(history: &History, limit: usize) -> DynResult {
+    let project_paths = get_project_paths();
+    let mut history = history.clone();
+    history.truncate(limit);
+    history.data_fingerprint = Some(fingerprint(&fs::read_to_string(&project_paths.data_path)?));
+    let stringified_history = serde_json::to_string(&history)?;
+    fs::write(&project_paths.history_path, stringified_history)?;
+
+    Ok(())
+}
+
+/// Returns the length and FNV-1a hash of `contents`, to tell whether a file has changed.
+fn fingerprint(contents: &str) -> String {
+    let hash = contents
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3));
+    format!("{}:{:016x}", contents.len(), hash)
+}
+
+/// Load the configuration from the config file, falling back to the defaults when there is no file.
+/// Settings missing from the file keep their default values, and key bindings missing from it come from the
+/// selected keymap preset.
+pub fn load_config() -> Result<Config, Box<dyn Error>> {
+    let project_paths = get_project_paths();
+
+    if !Path::new(&project_paths.config_path).exists() {
+        return Ok(Config::default());
+    }
+
+    let stringified_config = fs::read_to_string(&project_paths.config_path)?;
+    let config = Config::from_json(&stringified_config)
+        .map_err(|error| format!("Invalid config file {}: {}", project_paths.config_path.display(), error))?;
+
+    // Bindings that clash within a mode would make one of the actions impossible to use.
+    let conflicts = Keymap::new(&config.keybindings).conflicts();
+    if !conflicts.is_empty() {
+        return Err(format!(
+            "Conflicting key bindings in {}:\n  {}",
+            project_paths.config_path.display(),
+            conflicts.join("\n  ")
+        )
+        .into());
+    }
+
+    Ok(config)
+}
+
+/// Load the user's themes from the `.json` files in the themes directory, sorted by name. Each theme is named
+/// after its file, and a theme with the name of a built-in one replaces it.
+pub fn load_themes() -> Result<Vec<Theme>, Box<dyn Error>> {
+    let project_paths = get_project_paths();
+
+    if !project_paths.themes_path.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut themes = Vec::new();
+    for entry in fs::read_dir(&project_paths.themes_path)? {
+        let path = entry?.path();
+        if path.extension().is_none_or(|extension| extension != "json") {
+            continue;
+        }
+
+        let stringified_theme = fs::read_to_string(&path)?;
+        let theme: Theme = serde_path_to_error::deserialize(&mut serde_json::Deserializer::from_str(&stringified_theme))
+            .map_err(|error| format!("Invalid theme file {}: {}", path.display(), error))?;
+        let name = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+        themes.push(Theme { name, ..theme });
+    }
+
+    themes.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(themes)
+}
+
+/// Save the tasks, trash, archive and recent undo history of the running application.
+pub fn save_all(app: &App) -> DynResult {
+    save_task(&app.task)?;
+    save_trash(&app.trash)?;
+    save_archive(&app.archive)?;
+    save_history(&app.history, app.config.settings.saved_history_limit)?;
+
+    Ok(())
+}
+
 /// Load the welcome message from a text file.
 pub fn load_welcome_message() -> Result<String, io::Error> {
     fs::read_to_string("src/welcome.txt")
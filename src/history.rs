@@ -0,0 +1,180 @@
+///----------history.rs----------///
+
+use crate::{ArchivedTask, Task, TrashedTask};
+use serde::{Deserialize, Serialize};
+
+/// A copy of the task list, and of the trash or archive if the mutation changes them, as they were on one side
+/// of a mutation.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Snapshot {
+    pub description: String,                // Short description of the mutation, e.g. "delete task".
+    pub task: Vec<Task>,                    // Tasks as they were at the time of the snapshot.
+    pub selected_task: Option<usize>,       // Selection at the time of the snapshot.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trash: Option<Vec<TrashedTask>>,    // Trash contents, if the mutation changes the trash.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archive: Option<Vec<ArchivedTask>>, // Archive contents, if the mutation changes the archive.
+}
+
+impl Snapshot {
+    /// Returns which collections the snapshot holds.
+    pub fn scope(&self) -> Scope {
+        match (&self.trash, &self.archive) {
+            (Some(_), _) => Scope::Trash,
+            (None, Some(_)) => Scope::Archive,
+            (None, None) => Scope::Tasks,
+        }
+    }
+}
+
+/// What a mutation changes, and so what its snapshot holds. The task list and selection are always kept.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Scope {
+    /// Only the task list, such as checking or moving tasks.
+    Tasks,
+    /// The task list and the trash, such as deleting or restoring tasks.
+    Trash,
+    /// The task list and the archive, such as archiving completed tasks.
+    Archive,
+}
+
+/// Undo and redo stacks of task list snapshots.
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct History {
+    pub undo: Vec<Snapshot>,
+    pub redo: Vec<Snapshot>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_fingerprint: Option<String>, // Length and hash of the `data.json` the snapshots were taken against.
+    #[serde(skip)]
+    depth: usize, // Groups started and not yet ended, as a group can be started inside another.
+    #[serde(skip)]
+    grouped: usize, // Mutations recorded since the outermost open group was started.
+}
+
+impl History {
+    /// Records the state before a mutation. Recording a new mutation clears the redo stack. While a group is open
+    /// only the state before its first mutation is kept.
+    pub fn record(&mut self, snapshot: Snapshot, limit: usize) {
+        if self.depth > 0 {
+            self.grouped += 1;
+            if self.grouped > 1 {
+                return;
+            }
+        }
+        self.undo.push(snapshot);
+        self.redo.clear();
+        self.truncate(limit);
+    }
+
+    /// Takes the most recent snapshot to undo, pushing `current` onto the redo stack in its place.
+    pub fn undo(&mut self, mut current: Snapshot) -> Option<Snapshot> {
+        let snapshot = self.undo.pop()?;
+        current.description = snapshot.description.clone();
+        self.redo.push(current);
+        Some(snapshot)
+    }
+
+    /// Takes the most recently undone snapshot, pushing `current` back onto the undo stack.
+    pub fn redo(&mut self, mut current: Snapshot) -> Option<Snapshot> {
+        let snapshot = self.redo.pop()?;
+        current.description = snapshot.description.clone();
+        self.undo.push(current);
+        Some(snapshot)
+    }
+
+    /// Starts a group of mutations that are undone in a single step, such as the deletions of `3dd`.
+    pub fn begin_group(&mut self) {
+        if self.depth == 0 {
+            self.grouped = 0;
+        }
+        self.depth += 1;
+    }
+
+    /// Ends the group of mutations. Once the outermost group ends, how many mutations it had is added to the
+    /// description of its undo entry.
+    pub fn end_group(&mut self) {
+        self.depth = self.depth.saturating_sub(1);
+        if self.depth > 0 || self.grouped < 2 {
+            return;
+        }
+        if let Some(snapshot) = self.undo.last_mut() {
+            snapshot.description = format!("{} ({}×)", snapshot.description, self.grouped);
+        }
+    }
+
+    /// Drops the oldest undo entries so that at most `limit` remain.
+    pub fn truncate(&mut self, limit: usize) {
+        if self.undo.len() > limit {
+            self.undo.drain(..self.undo.len() - limit);
+        }
+        if self.redo.len() > limit {
+            self.redo.drain(..self.redo.len() - limit);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns a snapshot of a list of `tasks` tasks, with nothing selected.
+    fn snapshot(description: &str, tasks: usize) -> Snapshot {
+        Snapshot {
+            description: description.to_string(),
+            task: crate::test_tasks(&vec!["task"; tasks]),
+            selected_task: None,
+            trash: None,
+            archive: None,
+        }
+    }
+
+    #[test]
+    fn group_is_undone_in_one_step_when_the_stack_is_full() {
+        let limit = 5;
+        let mut history = History::default();
+        for tasks in (6..=10).rev() {
+            history.record(snapshot("delete task", tasks), limit);
+        }
+
+        // Deleting three of the five remaining tasks with `3dd`.
+        history.begin_group();
+        for tasks in (3..=5).rev() {
+            history.record(snapshot("delete task", tasks), limit);
+        }
+        history.end_group();
+
+        assert_eq!(history.undo.len(), limit);
+        assert_eq!(history.undo[limit - 1].description, "delete task (3×)");
+        let undone = history.undo(snapshot("", 2)).expect("an undo entry");
+        assert_eq!(undone.task.len(), 5);
+        assert_eq!(history.redo.len(), 1);
+    }
+
+    #[test]
+    fn nested_groups_end_with_the_outermost() {
+        let mut history = History::default();
+        history.begin_group();
+        history.record(snapshot("tag task #work", 3), 10);
+        history.begin_group();
+        history.record(snapshot("tag task #work", 3), 10);
+        history.end_group();
+        history.record(snapshot("tag task #work", 3), 10);
+        history.end_group();
+
+        assert_eq!(history.undo.len(), 1);
+        assert_eq!(history.undo[0].description, "tag task #work (3×)");
+    }
+
+    #[test]
+    fn single_mutation_in_a_group_keeps_its_description() {
+        let mut history = History::default();
+        history.begin_group();
+        history.record(snapshot("check task", 1), 10);
+        history.end_group();
+        history.record(snapshot("uncheck task", 1), 10);
+
+        assert_eq!(history.undo.len(), 2);
+        assert_eq!(history.undo[0].description, "check task");
+        assert_eq!(history.undo[1].description, "uncheck task");
+    }
+}
@@ -0,0 +1,437 @@
+///----------keymap.rs----------///
+
+use crate::{
+    action::Action,
+    config::KeyBindings,
+    keys::{KeyPress, KeySequence, Keys},
+    InputMode,
+};
+use crossterm::event::{KeyCode, KeyModifiers};
+
+/// Result of looking up the keys typed so far in the keymap.
+pub enum KeyMatch {
+    /// The keys typed complete a binding for this action.
+    Matched(Action),
+    /// The keys typed start a longer binding, so wait for the next key.
+    Pending,
+    /// No binding in the current mode uses the keys typed.
+    Unmatched,
+}
+
+/// Resolves keys to actions, with a separate set of bindings for every `InputMode`.
+#[derive(Debug, Default)]
+pub struct Keymap {
+    bindings: Vec<(InputMode, Keys, Action)>,
+}
+
+impl Keymap {
+    /// Builds the keymap from the configured key bindings, deciding which bindings are active in which mode.
+    pub fn new(keybindings: &KeyBindings) -> Self {
+        let mut keymap = Self::default();
+        let kb = keybindings;
+
+        keymap.bind_all(
+            InputMode::Normal,
+            &[
+                (&kb.new_task, Action::NewTask),
+                (&kb.new_task_above, Action::NewTaskAbove),
+                (&kb.new_task_below, Action::NewTaskBelow),
+                (&kb.exit_app, Action::ExitApp),
+                (&kb.list_up, Action::ListUp),
+                (&kb.list_down, Action::ListDown),
+                (&kb.list_top, Action::ListTop),
+                (&kb.list_bottom, Action::ListBottom),
+                (&kb.check_and_uncheck_task, Action::CheckAndUncheckTask),
+                (&kb.delete_task, Action::DeleteTask),
+                (&kb.move_task_up, Action::MoveTaskUp),
+                (&kb.move_task_down, Action::MoveTaskDown),
+                (&kb.move_task_top, Action::MoveTaskTop),
+                (&kb.move_task_bottom, Action::MoveTaskBottom),
+                (&kb.edit_task, Action::EditTask),
+                (&kb.undo, Action::Undo),
+                (&kb.redo, Action::Redo),
+                (&kb.view_trash, Action::ViewTrash),
+                (&kb.archive_completed, Action::ArchiveCompleted),
+                (&kb.view_archive, Action::ViewArchive),
+                (&kb.view_stats, Action::ViewStats),
+                (&kb.toggle_mark, Action::ToggleMark),
+                (&kb.visual_mode, Action::VisualMode),
+                (&kb.clear_marks, Action::ClearMarks),
+                (&kb.cycle_priority, Action::CyclePriority),
+                (&kb.tag_task, Action::TagTask),
+                (&kb.search, Action::Search),
+                (&kb.search_next, Action::SearchNext),
+                (&kb.search_previous, Action::SearchPrevious),
+                (&kb.save_to_disk, Action::SaveToDisk),
+                (&kb.help, Action::Help),
+                (&kb.message_history, Action::MessageHistory),
+                (&kb.cycle_theme, Action::CycleTheme),
+                (&kb.toggle_details, Action::ToggleDetails),
+                (&kb.scroll_details_up, Action::ScrollDetailsUp),
+                (&kb.scroll_details_down, Action::ScrollDetailsDown),
+                (&kb.command_palette, Action::CommandPalette),
+            ],
+        );
+
+        keymap.bind_all(
+            InputMode::Visual,
+            &[
+                (&kb.exit_view, Action::ExitView),
+                (&kb.list_up, Action::ListUp),
+                (&kb.list_down, Action::ListDown),
+                (&kb.list_top, Action::ListTop),
+                (&kb.list_bottom, Action::ListBottom),
+                (&kb.search_next, Action::SearchNext),
+                (&kb.search_previous, Action::SearchPrevious),
+                (&kb.toggle_mark, Action::ToggleMark),
+                (&kb.visual_mode, Action::VisualMode),
+                (&kb.check_and_uncheck_task, Action::CheckAndUncheckTask),
+                (&kb.delete_task, Action::DeleteTask),
+                (&kb.move_task_up, Action::MoveTaskUp),
+                (&kb.move_task_down, Action::MoveTaskDown),
+                (&kb.move_task_top, Action::MoveTaskTop),
+                (&kb.move_task_bottom, Action::MoveTaskBottom),
+                (&kb.cycle_priority, Action::CyclePriority),
+                (&kb.tag_task, Action::TagTask),
+                (&kb.help, Action::Help),
+                (&kb.message_history, Action::MessageHistory),
+                (&kb.cycle_theme, Action::CycleTheme),
+                (&kb.toggle_details, Action::ToggleDetails),
+                (&kb.scroll_details_up, Action::ScrollDetailsUp),
+                (&kb.scroll_details_down, Action::ScrollDetailsDown),
+                (&kb.command_palette, Action::CommandPalette),
+            ],
+        );
+
+        // The keys that save or abandon a new task also confirm or abandon a tag, a search or a command.
+        let text_modes = [
+            InputMode::Adding,
+            InputMode::Tagging,
+            InputMode::ArchiveSearch,
+            InputMode::Search,
+            InputMode::Palette,
+        ];
+        for mode in text_modes {
+            keymap.bind_all(
+                mode,
+                &[
+                    (&kb.save_task, Action::SaveTask),
+                    (&kb.exit_adding_mode, Action::ExitAddingMode),
+                    (&kb.input_cursor_left, Action::InputCursorLeft),
+                    (&kb.input_cursor_right, Action::InputCursorRight),
+                    (&kb.input_start, Action::InputStart),
+                    (&kb.input_end, Action::InputEnd),
+                    (&kb.input_kill_line, Action::InputKillLine),
+                ],
+            );
+        }
+
+        keymap.bind_all(
+            InputMode::Palette,
+            &[
+                (&kb.previous_command, Action::PreviousCommand),
+                (&kb.next_command, Action::NextCommand),
+            ],
+        );
+
+        keymap.bind_all(
+            InputMode::Trash,
+            &[
+                (&kb.exit_view, Action::ExitView),
+                (&kb.list_up, Action::ListUp),
+                (&kb.list_down, Action::ListDown),
+                (&kb.restore_task, Action::RestoreTask),
+                (&kb.purge_task, Action::PurgeTask),
+                (&kb.empty_trash, Action::EmptyTrash),
+                (&kb.undo, Action::Undo),
+                (&kb.redo, Action::Redo),
+                (&kb.help, Action::Help),
+                (&kb.message_history, Action::MessageHistory),
+                (&kb.cycle_theme, Action::CycleTheme),
+            ],
+        );
+
+        keymap.bind_all(
+            InputMode::Archive,
+            &[
+                (&kb.exit_view, Action::ExitView),
+                (&kb.list_up, Action::ListUp),
+                (&kb.list_down, Action::ListDown),
+                (&kb.search, Action::Search),
+                (&kb.help, Action::Help),
+                (&kb.message_history, Action::MessageHistory),
+                (&kb.cycle_theme, Action::CycleTheme),
+            ],
+        );
+
+        keymap.bind_all(
+            InputMode::Stats,
+            &[
+                (&kb.view_stats, Action::ViewStats),
+                (&kb.exit_view, Action::ExitView),
+                (&kb.help, Action::Help),
+                (&kb.message_history, Action::MessageHistory),
+                (&kb.cycle_theme, Action::CycleTheme),
+            ],
+        );
+
+        // The popups scroll with the list keys and close with the key that opened them.
+        let popups = [
+            (InputMode::Help, &kb.help, Action::Help),
+            (InputMode::Messages, &kb.message_history, Action::MessageHistory),
+        ];
+        for (mode, toggle, action) in popups {
+            keymap.bind_all(
+                mode,
+                &[
+                    (toggle, action),
+                    (&kb.exit_view, Action::ExitView),
+                    (&kb.list_up, Action::ListUp),
+                    (&kb.list_down, Action::ListDown),
+                    (&kb.list_top, Action::ListTop),
+                    (&kb.list_bottom, Action::ListBottom),
+                ],
+            );
+        }
+
+        keymap.bind_all(
+            InputMode::Confirm,
+            &[(&kb.confirm_yes, Action::Confirm), (&kb.confirm_no, Action::Cancel)],
+        );
+
+        // The cancel keys leave every mode.
+        for mode in InputMode::ALL {
+            keymap.bind_all(mode, &[(&kb.cancel, Action::Cancel)]);
+        }
+
+        keymap
+    }
+
+    /// Binds each of the keys to its action in `mode`.
+    fn bind_all(&mut self, mode: InputMode, bindings: &[(&Keys, Action)]) {
+        for (keys, action) in bindings {
+            self.bindings.push((mode, (*keys).clone(), *action));
+        }
+    }
+
+    /// Returns the keys bound in `mode` together with their actions, in the order they were bound.
+    pub fn bindings(&self, mode: InputMode) -> impl Iterator<Item = (&Keys, Action)> {
+        self.bindings
+            .iter()
+            .filter(move |(binding_mode, _, _)| *binding_mode == mode)
+            .map(|(_, keys, action)| (keys, *action))
+    }
+
+    /// Lists the bindings of every mode for the help overlay, as a heading followed by the keys and description
+    /// of each action. Modes with exactly the same bindings, such as the text fields, share one section.
+    pub fn help_sections(&self) -> Vec<(String, Vec<(String, &'static str)>)> {
+        let mut sections: Vec<(String, Vec<(String, &'static str)>)> = Vec::new();
+
+        for mode in InputMode::ALL {
+            let entries: Vec<(String, &'static str)> = self
+                .bindings(mode)
+                .filter(|(keys, _)| !keys.0.is_empty())
+                .map(|(keys, action)| (keys.to_string(), action.description()))
+                .collect();
+
+            if entries.is_empty() {
+                continue;
+            }
+
+            match sections.iter_mut().find(|(_, existing)| *existing == entries) {
+                Some((heading, _)) => {
+                    heading.push_str(", ");
+                    heading.push_str(&mode.label().to_lowercase());
+                }
+                None => sections.push((mode.label().to_string(), entries)),
+            }
+        }
+
+        sections
+    }
+
+    /// Returns a message for every clash between the keys of two different actions in the same mode: the same
+    /// keys bound to both, or the keys of one being the start of a longer binding of the other, which could then
+    /// never be typed. A clash found in several modes is reported once, listing the modes.
+    pub fn conflicts(&self) -> Vec<String> {
+        let mut found = Vec::new();
+
+        for mode in InputMode::ALL {
+            let sequences: Vec<(&KeySequence, Action)> = self
+                .bindings(mode)
+                .flat_map(|(keys, action)| keys.0.iter().map(move |sequence| (sequence, action)))
+                .collect();
+
+            for (index, &(first, first_action)) in sequences.iter().enumerate() {
+                for &(second, second_action) in &sequences[index + 1..] {
+                    if first_action == second_action {
+                        continue;
+                    }
+
+                    let (first_name, second_name) = (first_action.name(), second_action.name());
+                    let message = if first == second {
+                        format!("\"{}\" is bound to both {} and {}", first, first_name, second_name)
+                    } else if second.0.starts_with(&first.0) {
+                        format!("\"{}\" for {} hides \"{}\" for {}", first, first_name, second, second_name)
+                    } else if first.0.starts_with(&second.0) {
+                        format!("\"{}\" for {} hides \"{}\" for {}", second, second_name, first, first_name)
+                    } else {
+                        continue;
+                    };
+
+                    add_to_modes(&mut found, message, mode);
+                }
+            }
+        }
+
+        list_with_modes(found)
+    }
+
+    /// Returns a warning for every binding in a mode with a text field that starts with a plain character, which
+    /// can then no longer be typed into the field.
+    pub fn text_shadows(&self) -> Vec<String> {
+        let mut found = Vec::new();
+
+        for mode in InputMode::ALL.into_iter().filter(|mode| mode.accepts_text()) {
+            for (keys, action) in self.bindings(mode) {
+                for sequence in &keys.0 {
+                    let press = sequence.0[0];
+                    let plain = !press.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT);
+                    if plain && matches!(press.code, KeyCode::Char(_)) {
+                        let message = format!("\"{}\" for {} cannot be typed as text", sequence, action.name());
+                        add_to_modes(&mut found, message, mode);
+                    }
+                }
+            }
+        }
+
+        list_with_modes(found)
+    }
+
+    /// Returns whether `press` on its own completes or starts any binding in `mode`.
+    pub fn is_bound(&self, mode: InputMode, press: KeyPress) -> bool {
+        self.bindings(mode).any(|(keys, _)| keys.matches(&[press]) || keys.continues(&[press]))
+    }
+
+    /// Adds `press` to the pending key sequence and looks it up among the bindings of `mode`.
+    /// When the sequence leads nowhere it is dropped and `press` is tried again on its own. In modes with a text
+    /// field, a press that is not bound types or erases a character instead.
+    pub fn resolve(&self, mode: InputMode, pending: &mut Vec<KeyPress>, press: KeyPress) -> KeyMatch {
+        pending.push(press);
+
+        if let Some((_, action)) = self.bindings(mode).find(|(keys, _)| keys.matches(pending)) {
+            pending.clear();
+            return KeyMatch::Matched(action);
+        }
+
+        if self.bindings(mode).any(|(keys, _)| keys.continues(pending)) {
+            return KeyMatch::Pending;
+        }
+
+        let retry = pending.len() > 1;
+        pending.clear();
+        if retry {
+            return self.resolve(mode, pending, press);
+        }
+
+        match (mode.accepts_text(), press.code) {
+            (true, KeyCode::Char(c)) if !press.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => {
+                KeyMatch::Matched(Action::InsertChar(c))
+            }
+            (true, KeyCode::Backspace) => KeyMatch::Matched(Action::DeleteChar),
+            _ => KeyMatch::Unmatched,
+        }
+    }
+}
+
+/// Adds `mode` to the modes a message applies to, adding the message if it is new.
+fn add_to_modes(found: &mut Vec<(String, Vec<InputMode>)>, message: String, mode: InputMode) {
+    match found.iter_mut().find(|(existing, _)| *existing == message) {
+        Some((_, modes)) => modes.push(mode),
+        None => found.push((message, vec![mode])),
+    }
+}
+
+/// Appends the modes each message applies to, e.g. `... (in task list, visual selection)`.
+fn list_with_modes(found: Vec<(String, Vec<InputMode>)>) -> Vec<String> {
+    found
+        .into_iter()
+        .map(|(message, modes)| {
+            let modes: Vec<String> = modes.iter().map(|mode| mode.label().to_lowercase()).collect();
+            format!("{} (in {})", message, modes.join(", "))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::KeymapPreset;
+
+    fn keys(specs: &[&str]) -> Keys {
+        Keys::parse(specs).unwrap()
+    }
+
+    #[test]
+    fn reports_the_same_keys_on_two_actions() {
+        let keymap = Keymap::new(&KeyBindings {
+            undo: keys(&["F9"]),
+            redo: keys(&["F9"]),
+            ..KeyBindings::default()
+        });
+        let conflicts = keymap.conflicts();
+
+        assert!(
+            conflicts.iter().any(|message| message == "\"F9\" is bound to both undo and redo (in task list, trash)"),
+            "{:?}",
+            conflicts
+        );
+    }
+
+    #[test]
+    fn reports_a_binding_hidden_by_a_shorter_one() {
+        let keymap = Keymap::new(&KeyBindings {
+            list_top: keys(&["g g"]),
+            list_bottom: keys(&["g"]),
+            ..KeyBindings::default()
+        });
+        let conflicts = keymap.conflicts();
+
+        assert!(
+            conflicts.iter().any(|message| message.starts_with("\"g\" for list_bottom hides \"g g\" for list_top")),
+            "{:?}",
+            conflicts
+        );
+    }
+
+    #[test]
+    fn allows_the_same_keys_in_different_modes() {
+        // `restore_task` is only bound in the trash and `check_and_uncheck_task` only in the task lists.
+        let keymap = Keymap::new(&KeyBindings {
+            check_and_uncheck_task: keys(&["F9"]),
+            restore_task: keys(&["F9"]),
+            ..KeyBindings::default()
+        });
+
+        assert!(keymap.conflicts().iter().all(|message| !message.starts_with("\"F9\"")), "{:?}", keymap.conflicts());
+    }
+
+    #[test]
+    fn reports_plain_characters_in_text_modes() {
+        let keymap = Keymap::new(&KeyBindings { save_task: keys(&["Enter", "s"]), ..KeyBindings::default() });
+        let shadows = keymap.text_shadows();
+
+        assert_eq!(shadows.len(), 1, "{:?}", shadows);
+        assert!(shadows[0].starts_with("\"s\" for save_task cannot be typed as text (in "));
+        assert!(shadows[0].contains(&InputMode::Adding.label().to_lowercase()));
+    }
+
+    #[test]
+    fn presets_have_no_conflicts() {
+        for preset in [KeymapPreset::Default, KeymapPreset::Vim, KeymapPreset::Emacs] {
+            let keymap = Keymap::new(&KeyBindings::preset(preset));
+            assert_eq!(keymap.conflicts(), Vec::<String>::new(), "{:?}", preset);
+            assert_eq!(keymap.text_shadows(), Vec::<String>::new(), "{:?}", preset);
+        }
+    }
+}
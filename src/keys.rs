@@ -0,0 +1,325 @@
+///----------keys.rs----------///
+
+use crate::config::keycode_to_string;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+
+/// A single key press: a key together with the modifiers held while pressing it.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct KeyPress {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyPress {
+    /// Constructor for a key press. Shift is folded into the character for letters and symbols, so that
+    /// `K` and `S-k` describe the same press.
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let (code, modifiers) = match code {
+            KeyCode::Char(c) if modifiers.contains(KeyModifiers::SHIFT) => {
+                (KeyCode::Char(c.to_ascii_uppercase()), modifiers - KeyModifiers::SHIFT)
+            }
+            KeyCode::BackTab => (code, modifiers - KeyModifiers::SHIFT),
+            _ => (code, modifiers),
+        };
+
+        Self { code, modifiers }
+    }
+
+    /// Parses a single key press such as `a`, `Enter`, `C-s` or `M-Up`.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = spec;
+
+        // Modifier prefixes are only stripped while a key name remains after them, so `-` and `C--` still parse.
+        while rest.len() > 2 && rest.as_bytes()[1] == b'-' {
+            modifiers |= match &rest[..1] {
+                "C" => KeyModifiers::CONTROL,
+                "M" | "A" => KeyModifiers::ALT,
+                "S" => KeyModifiers::SHIFT,
+                _ => break,
+            };
+            rest = &rest[2..];
+        }
+
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match rest.to_ascii_lowercase().as_str() {
+                "space" => KeyCode::Char(' '),
+                "enter" | "return" => KeyCode::Enter,
+                "esc" | "escape" => KeyCode::Esc,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" | "ins" => KeyCode::Insert,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                name => match name.strip_prefix('f').and_then(|number| number.parse().ok()) {
+                    Some(number) => KeyCode::F(number),
+                    None => return Err(format!("unknown key \"{}\" in \"{}\"", rest, spec)),
+                },
+            },
+        };
+
+        Ok(Self::new(code, modifiers))
+    }
+}
+
+impl From<KeyEvent> for KeyPress {
+    fn from(event: KeyEvent) -> Self {
+        Self::new(event.code, event.modifiers)
+    }
+}
+
+/// Displays the press for the UI, with arrows for the arrow keys.
+impl fmt::Display for KeyPress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", modifier_prefix(self.modifiers), keycode_to_string(self.code))
+    }
+}
+
+/// Returns the `C-`, `M-` and `S-` prefixes for the held modifiers.
+fn modifier_prefix(modifiers: KeyModifiers) -> String {
+    let mut prefix = String::new();
+    if modifiers.contains(KeyModifiers::CONTROL) {
+        prefix.push_str("C-");
+    }
+    if modifiers.contains(KeyModifiers::ALT) {
+        prefix.push_str("M-");
+    }
+    if modifiers.contains(KeyModifiers::SHIFT) {
+        prefix.push_str("S-");
+    }
+    prefix
+}
+
+/// Writes a press in the syntax `KeyPress::parse` reads, spelling out keys that `Display` shows as symbols.
+fn spec_string(press: &KeyPress) -> String {
+    let name = match press.code {
+        KeyCode::Up => "Up".to_string(),
+        KeyCode::Down => "Down".to_string(),
+        KeyCode::Left => "Left".to_string(),
+        KeyCode::Right => "Right".to_string(),
+        KeyCode::PageUp => "PageUp".to_string(),
+        KeyCode::PageDown => "PageDown".to_string(),
+        KeyCode::BackTab => "BackTab".to_string(),
+        code => keycode_to_string(code),
+    };
+
+    format!("{}{}", modifier_prefix(press.modifiers), name)
+}
+
+/// A sequence of key presses that must be typed one after the other, such as `g g`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct KeySequence(pub Vec<KeyPress>);
+
+impl KeySequence {
+    /// Parses a space separated sequence of key presses such as `g g` or `C-x C-s`.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let presses = spec
+            .split_whitespace()
+            .map(KeyPress::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if presses.is_empty() {
+            return Err("empty key binding".to_string());
+        }
+
+        Ok(Self(presses))
+    }
+}
+
+impl fmt::Display for KeySequence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (index, press) in self.0.iter().enumerate() {
+            if index > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}", press)?;
+        }
+        Ok(())
+    }
+}
+
+/// All the key sequences bound to one action. Any of them triggers the action.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Keys(pub Vec<KeySequence>);
+
+impl Keys {
+    /// Parses a list of key sequences, e.g. `["Up", "k"]`.
+    pub fn parse(specs: &[&str]) -> Result<Self, String> {
+        specs
+            .iter()
+            .map(|spec| KeySequence::parse(spec))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Self)
+    }
+
+    /// Returns whether any of the sequences is exactly `pressed`.
+    pub fn matches(&self, pressed: &[KeyPress]) -> bool {
+        self.0.iter().any(|sequence| sequence.0 == pressed)
+    }
+
+    /// Returns whether any of the sequences starts with `pressed` and continues after it.
+    pub fn continues(&self, pressed: &[KeyPress]) -> bool {
+        self.0
+            .iter()
+            .any(|sequence| sequence.0.len() > pressed.len() && sequence.0.starts_with(pressed))
+    }
+}
+
+/// Displays every sequence, separated by slashes.
+impl fmt::Display for Keys {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (index, sequence) in self.0.iter().enumerate() {
+            if index > 0 {
+                write!(f, "/")?;
+            }
+            write!(f, "{}", sequence)?;
+        }
+        Ok(())
+    }
+}
+
+/// Key bindings are written as a single string such as `"C-s"`, or a list such as `["Up", "k"]`.
+/// The older `KeyCode` format, e.g. `{"Char": "a"}`, is still accepted.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum KeysRepr {
+    One(String),
+    Many(Vec<String>),
+    Code(KeyCode),
+}
+
+impl<'de> Deserialize<'de> for Keys {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match KeysRepr::deserialize(deserializer)? {
+            KeysRepr::One(spec) => Keys::parse(&[spec.as_str()]),
+            KeysRepr::Many(specs) => Keys::parse(&specs.iter().map(String::as_str).collect::<Vec<_>>()),
+            KeysRepr::Code(code) => Ok(Keys(vec![KeySequence(vec![KeyPress::new(code, KeyModifiers::NONE)])])),
+        }
+        .map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for Keys {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let specs: Vec<String> = self
+            .0
+            .iter()
+            .map(|sequence| sequence.0.iter().map(spec_string).collect::<Vec<_>>().join(" "))
+            .collect();
+
+        match specs.as_slice() {
+            [spec] => serializer.serialize_str(spec),
+            _ => specs.serialize(serializer),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(code: KeyCode, modifiers: KeyModifiers) -> KeyPress {
+        KeyPress { code, modifiers }
+    }
+
+    fn round_trip(keys: &Keys) -> Keys {
+        serde_json::from_str(&serde_json::to_string(keys).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn parses_plain_and_named_keys() {
+        assert_eq!(KeyPress::parse("a"), Ok(press(KeyCode::Char('a'), KeyModifiers::NONE)));
+        assert_eq!(KeyPress::parse("Space"), Ok(press(KeyCode::Char(' '), KeyModifiers::NONE)));
+        assert_eq!(KeyPress::parse("enter"), Ok(press(KeyCode::Enter, KeyModifiers::NONE)));
+        assert_eq!(KeyPress::parse("PageUp"), Ok(press(KeyCode::PageUp, KeyModifiers::NONE)));
+        assert_eq!(KeyPress::parse("F5"), Ok(press(KeyCode::F(5), KeyModifiers::NONE)));
+        assert_eq!(KeyPress::parse("-"), Ok(press(KeyCode::Char('-'), KeyModifiers::NONE)));
+    }
+
+    #[test]
+    fn parses_modifiers() {
+        assert_eq!(KeyPress::parse("C-s"), Ok(press(KeyCode::Char('s'), KeyModifiers::CONTROL)));
+        assert_eq!(KeyPress::parse("C--"), Ok(press(KeyCode::Char('-'), KeyModifiers::CONTROL)));
+        assert_eq!(KeyPress::parse("A-Up"), Ok(press(KeyCode::Up, KeyModifiers::ALT)));
+        assert_eq!(
+            KeyPress::parse("C-M-Left"),
+            Ok(press(KeyCode::Left, KeyModifiers::CONTROL | KeyModifiers::ALT))
+        );
+        assert_eq!(KeyPress::parse("S-Tab"), Ok(press(KeyCode::Tab, KeyModifiers::SHIFT)));
+    }
+
+    #[test]
+    fn folds_shift_into_characters() {
+        assert_eq!(KeyPress::parse("S-k"), KeyPress::parse("K"));
+        assert_eq!(KeyPress::parse("C-S-k"), Ok(press(KeyCode::Char('K'), KeyModifiers::CONTROL)));
+        assert_eq!(KeyPress::parse("S-BackTab"), Ok(press(KeyCode::BackTab, KeyModifiers::NONE)));
+        assert_eq!(
+            KeyPress::from(KeyEvent::new(KeyCode::Char('k'), KeyModifiers::SHIFT)),
+            press(KeyCode::Char('K'), KeyModifiers::NONE)
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        assert!(KeyPress::parse("Foo").is_err());
+        assert!(KeyPress::parse("X-a").is_err());
+        assert!(KeyPress::parse("Fx").is_err());
+        assert!(KeySequence::parse("  ").is_err());
+        assert!(Keys::parse(&["g", "C-Nope"]).is_err());
+    }
+
+    #[test]
+    fn parses_sequences() {
+        let keys = Keys::parse(&["g g", "C-x C-s"]).unwrap();
+        let g = press(KeyCode::Char('g'), KeyModifiers::NONE);
+
+        assert_eq!(keys.0[0], KeySequence(vec![g, g]));
+        assert!(keys.matches(&[g, g]));
+        assert!(keys.continues(&[g]));
+        assert!(!keys.continues(&[g, g]));
+        assert_eq!(keys.to_string(), "g g/C-x C-s");
+    }
+
+    #[test]
+    fn keys_round_trip_through_json() {
+        let specs = ["a", "Space", "C--", "g g", "C-x C-s", "S-k", "PageUp", "PageDown", "M-Up", "BackTab", "F12", "Enter"];
+        for spec in specs {
+            let keys = Keys::parse(&[spec]).unwrap();
+            assert_eq!(round_trip(&keys), keys, "{}", spec);
+        }
+
+        let many = Keys::parse(&specs).unwrap();
+        assert_eq!(round_trip(&many), many);
+    }
+
+    #[test]
+    fn serializes_one_sequence_as_a_string() {
+        assert_eq!(serde_json::to_string(&Keys::parse(&["S-k"]).unwrap()).unwrap(), "\"K\"");
+        assert_eq!(serde_json::to_string(&Keys::parse(&["Up", "k"]).unwrap()).unwrap(), "[\"Up\",\"k\"]");
+        assert_eq!(serde_json::to_string(&Keys::parse(&["PageUp"]).unwrap()).unwrap(), "\"PageUp\"");
+    }
+
+    #[test]
+    fn reads_the_legacy_keycode_format() {
+        let keys: Keys = serde_json::from_str(r#"{"Char": "a"}"#).unwrap();
+        assert_eq!(keys, Keys::parse(&["a"]).unwrap());
+
+        let keys: Keys = serde_json::from_str(r#""PageUp""#).unwrap();
+        assert_eq!(keys, Keys::parse(&["PageUp"]).unwrap());
+
+        let keys: Keys = serde_json::from_str(r#"{"F": 2}"#).unwrap();
+        assert_eq!(keys, Keys::parse(&["F2"]).unwrap());
+    }
+}
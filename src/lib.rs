@@ -1,17 +1,35 @@
 ///----------lib.rs----------///
 
 pub mod utils;
+pub mod action;
+pub mod cli;
+pub mod color;
 pub mod config;
 pub mod events;
 pub mod file_handler;
+pub mod history;
+pub mod keymap;
+pub mod keys;
+pub mod palette;
+pub mod stats;
+pub mod theme;
 pub mod widget;
 
-use config::Config;
+use action::Confirmation;
+use config::{ColorSupport, Config};
+use history::{History, Scope, Snapshot};
+use keymap::Keymap;
+use keys::KeyPress;
+use theme::{Theme, BUILTIN_THEMES};
 use serde::{Deserialize, Serialize};
-use std::{error::Error, io::Stdout};
+use std::{
+    error::Error,
+    io::Stdout,
+    time::{SystemTime, UNIX_EPOCH},
+};
 use tui::{
     backend::CrosstermBackend,
-    style::{Modifier, Style},
+    style::Style,
     Frame, Terminal,
 };
 
@@ -21,11 +39,33 @@ pub type CrossTerminal = Terminal<CrosstermBackend<Stdout>>;
 
 pub type TerminalFrame<'a> = Frame<'a, CrosstermBackend<Stdout>>;
 
+/// Returns the current time as seconds since the Unix epoch.
+pub fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
 /// Represents a single task in the task list.
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Task {
     pub title: String,
     pub completed: bool,
+    #[serde(default)]
+    pub created_at: Option<u64>, // Unix timestamp of when the task was added, if known.
+    #[serde(default)]
+    pub completed_at: Option<u64>, // Unix timestamp of when the task was last checked.
+    #[serde(default)]
+    pub priority: Option<Priority>, // Priority of the task, if one has been set.
+    #[serde(default)]
+    pub tags: Vec<String>, // Tags attached to the task, without the leading '#'.
+    #[serde(default)]
+    pub notes: String, // Free text about the task, empty if there is none.
+    #[serde(default)]
+    pub due: Option<u64>, // Unix timestamp of the start of the UTC day the task is due, if it has a due date.
+    #[serde(skip)]
+    pub marked: bool, // Whether the task is marked for a batch operation. Not saved.
 }
 
 impl Task {
@@ -34,6 +74,48 @@ impl Task {
         Self {
             title,
             completed: false, // Tasks start as not completed by default.
+            created_at: Some(unix_timestamp()),
+            completed_at: None,
+            priority: None,
+            tags: Vec::new(),
+            notes: String::new(),
+            due: None,
+            marked: false,
+        }
+    }
+
+    /// Checks or unchecks the task, keeping track of when it was completed.
+    pub fn set_completed(&mut self, completed: bool) {
+        self.completed = completed;
+        self.completed_at = if completed { Some(unix_timestamp()) } else { None };
+    }
+}
+
+/// Priority levels that can be assigned to a task.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Priority {
+    Low,
+    Medium,
+    High,
+}
+
+impl Priority {
+    /// Returns the priority after `priority` when cycling through the levels, wrapping back to no priority.
+    pub fn next(priority: Option<Priority>) -> Option<Priority> {
+        match priority {
+            None => Some(Priority::Low),
+            Some(Priority::Low) => Some(Priority::Medium),
+            Some(Priority::Medium) => Some(Priority::High),
+            Some(Priority::High) => None,
+        }
+    }
+
+    /// Returns the lowercase name of the priority.
+    pub fn label(self) -> &'static str {
+        match self {
+            Priority::Low => "low",
+            Priority::Medium => "medium",
+            Priority::High => "high",
         }
     }
 }
@@ -53,74 +135,603 @@ impl TaskList {
     }
 }
 
+/// A deleted task kept in the trash until it is restored or purged.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TrashedTask {
+    pub task: Task,
+    pub deleted_at: u64, // Unix timestamp of when the task was deleted.
+}
+
+impl TrashedTask {
+    /// Wraps a task that is being deleted now.
+    pub fn new(task: Task) -> Self {
+        Self {
+            task,
+            deleted_at: unix_timestamp(),
+        }
+    }
+}
+
+/// Represents the contents of the trash file.
+#[derive(Serialize, Deserialize, Default)]
+pub struct Trash {
+    pub task: Vec<TrashedTask>,
+}
+
+impl Trash {
+    /// Constructor for creating the trash from a slice of trashed tasks.
+    pub fn new(task: &[TrashedTask]) -> Self {
+        Self { task: task.to_vec() }
+    }
+
+    /// Drops every task that has been in the trash for longer than `retention_days`. Zero keeps tasks forever.
+    pub fn purge_expired(&mut self, retention_days: u64) {
+        if retention_days == 0 {
+            return;
+        }
+
+        let cutoff = unix_timestamp().saturating_sub(retention_days * 24 * 60 * 60);
+        self.task.retain(|trashed| trashed.deleted_at >= cutoff);
+    }
+}
+
+/// A completed task moved out of the active list into the archive.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ArchivedTask {
+    pub task: Task,
+    pub archived_at: u64, // Unix timestamp of when the task was archived.
+}
+
+impl ArchivedTask {
+    /// Wraps a task that is being archived now.
+    pub fn new(task: Task) -> Self {
+        Self {
+            task,
+            archived_at: unix_timestamp(),
+        }
+    }
+}
+
+/// Represents the contents of the archive file.
+#[derive(Serialize, Deserialize, Default)]
+pub struct Archive {
+    pub task: Vec<ArchivedTask>,
+}
+
+impl Archive {
+    /// Constructor for creating the archive from a slice of archived tasks.
+    pub fn new(task: &[ArchivedTask]) -> Self {
+        Self { task: task.to_vec() }
+    }
+}
+
+/// Number of messages kept in the message history.
+const MESSAGE_HISTORY_LIMIT: usize = 100;
+
+/// How serious a message in the status line is, which decides its color.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MessageLevel {
+    Info,
+    Warning,
+    Error,
+}
+
+/// Feedback or an error reported in the status line.
+#[derive(Clone, Debug)]
+pub struct Message {
+    pub text: String,
+    pub level: MessageLevel,
+    /// Unix timestamp in seconds of when the message was reported.
+    pub created_at: u64,
+}
+
 /// Enum representing the possible states of the input field in the application.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum InputMode {
     /// Normal browsing through the task list.
     Normal,
-    /// Adding a new task to the list.
+    /// Adding a new task to the list, or editing an existing one.
     Adding,
-    /// To confirm the user want's to quit
-    ConfirmQuit,
+    /// Answering yes or no before an action that needs confirmation is performed.
+    Confirm,
+    /// Browsing the trash to restore or purge deleted tasks.
+    Trash,
+    /// Browsing the read-only archive of completed tasks.
+    Archive,
+    /// Typing a search query to filter the archive.
+    ArchiveSearch,
+    /// Selecting a range of tasks to mark for a batch operation.
+    Visual,
+    /// Typing a tag to add to or remove from the targeted tasks.
+    Tagging,
+    /// Typing a search query, selecting the first matching task as it is typed.
+    Search,
+    /// Reading the list of key bindings on top of the mode it was opened from.
+    Help,
+    /// Typing the name of a command to run in the command palette.
+    Palette,
+    /// Reading the history of messages on top of the mode it was opened from.
+    Messages,
+    /// Looking at the statistics screen instead of the task list.
+    Stats,
+}
+
+impl InputMode {
+    /// Every mode, in the order they are listed in help.
+    pub const ALL: [InputMode; 13] = [
+        InputMode::Normal,
+        InputMode::Visual,
+        InputMode::Adding,
+        InputMode::Tagging,
+        InputMode::Search,
+        InputMode::Trash,
+        InputMode::Archive,
+        InputMode::ArchiveSearch,
+        InputMode::Stats,
+        InputMode::Confirm,
+        InputMode::Palette,
+        InputMode::Help,
+        InputMode::Messages,
+    ];
+
+    /// Returns the name of the mode as shown in help.
+    pub fn label(self) -> &'static str {
+        match self {
+            InputMode::Normal => "Task list",
+            InputMode::Adding => "Adding or editing a task",
+            InputMode::Confirm => "Confirmation",
+            InputMode::Trash => "Trash",
+            InputMode::Archive => "Archive",
+            InputMode::ArchiveSearch => "Searching the archive",
+            InputMode::Visual => "Visual selection",
+            InputMode::Tagging => "Tagging",
+            InputMode::Search => "Searching tasks",
+            InputMode::Help => "Help",
+            InputMode::Palette => "Command palette",
+            InputMode::Messages => "Message history",
+            InputMode::Stats => "Statistics",
+        }
+    }
+
+    /// Returns whether the mode has a text field that unbound character keys type into.
+    pub fn accepts_text(self) -> bool {
+        matches!(
+            self,
+            InputMode::Adding | InputMode::Tagging | InputMode::ArchiveSearch | InputMode::Search | InputMode::Palette
+        )
+    }
 }
 
 /// Main application state structure holding all data about the application's runtime state.
 pub struct App {
     /// Buffer for user input when adding new tasks.
     pub input: String,
+    /// Position of the cursor in the active input field, in characters.
+    pub cursor: usize,
     /// Current mode of input handling, either adding a task or browsing.
     pub input_mode: InputMode,
     /// Dynamic list of tasks currently managed by the application.
     pub task: Vec<Task>,
     /// Flag indicating whether the application should exit.
     pub should_exit: bool,
+    /// Whether saving failed when the user last tried to exit, so that quitting again exits without saving.
+    pub save_failed: bool,
     /// Index of the currently selected task in the list, if any.
     pub selected_task: Option<usize>,
+    /// Index of the first task shown in the list, scrolled to keep the selection in view.
+    pub task_offset: usize,
+    /// Deleted tasks, most recently deleted last.
+    pub trash: Vec<TrashedTask>,
+    /// Index of the currently selected task in the trash view, if any.
+    pub selected_trash: Option<usize>,
+    /// Completed tasks moved out of the list, oldest first.
+    pub archive: Vec<ArchivedTask>,
+    /// Search query filtering the archive view.
+    pub archive_query: String,
+    /// Index of the currently selected entry among the archive search results, if any.
+    pub selected_archive: Option<usize>,
+    /// Index of the task where visual mode was entered, while in visual mode.
+    pub visual_anchor: Option<usize>,
+    /// Position at which the next task saved in adding mode will be inserted.
+    pub insert_index: usize,
+    /// Index of the task whose title is being edited, if adding mode was entered to edit a task.
+    pub editing_task: Option<usize>,
+    /// Undo and redo history of task mutations.
+    pub history: History,
+    /// Search query used to find tasks in the list.
+    pub task_query: String,
+    /// Selection from before the current search started, restored if the search is abandoned.
+    pub search_origin: Option<usize>,
+    /// Repeat count typed before an action, e.g. the 5 in `5j`.
+    pub count: Option<usize>,
+    /// Keys typed so far of a multi-key binding that has not been completed yet.
+    pub pending_keys: Vec<KeyPress>,
+    /// Mode the help or message history popup was opened from, returned to when it is closed.
+    pub popup_origin: InputMode,
+    /// Number of lines the help or message history popup is scrolled down by.
+    pub popup_scroll: usize,
+    /// Whether the task details pane has been toggled from its automatic visibility, which is shown on wide
+    /// terminals only.
+    pub detail_toggled: bool,
+    /// Number of rows the task details pane is scrolled down by.
+    pub detail_scroll: usize,
+    /// Index of the task the details pane was scrolled for, so that the scroll is reset for another task.
+    pub detail_task: Option<usize>,
+    /// Text typed into the command palette: a command name, optionally followed by an argument.
+    pub command_input: String,
+    /// Index of the selected command among those matching `command_input`.
+    pub selected_command: usize,
+    /// Mode the command palette was opened from, in which the chosen command runs.
+    pub palette_origin: InputMode,
+    /// Action waiting for the user to confirm it, while in confirmation mode.
+    pub confirmation: Option<Confirmation>,
+    /// Feedback and errors reported this session, oldest first. The last one is shown in the status line until
+    /// it expires.
+    pub messages: Vec<Message>,
     /// Configuration settings for the application.
     pub config: Config,
+    /// Key bindings of every mode, resolved from the configuration.
+    pub keymap: Keymap,
+    /// Themes that can be switched to: the built-in ones followed by the user's theme files.
+    pub themes: Vec<Theme>,
+    /// Active theme, with the colors set in the configuration applied on top of it.
+    pub theme: Theme,
+    /// Colors the terminal can show, which the active theme is reduced to.
+    pub color_support: ColorSupport,
 }
 
 impl App {
     /// Constructor for creating a new App instance from a list of tasks and configuration settings.
+    /// Bindings that stop characters from being typed into text fields are reported in the first message.
     pub fn new(task: &[Task], config: Config) -> Self {
-        Self {
+        let keymap = Keymap::new(&config.keybindings);
+        let shadows = keymap.text_shadows();
+        let color_support = color::detect(config.settings.color_support);
+
+        let mut app = Self {
             task: task.to_vec(),
-            selected_task: Some(0), // Start with the first task selected.
+            selected_task: if task.is_empty() { None } else { Some(0) }, // Start with the first task selected.
+            task_offset: 0,
+            trash: Vec::new(),
+            selected_trash: None,
+            archive: Vec::new(),
+            archive_query: String::new(),
+            selected_archive: None,
+            visual_anchor: None,
+            insert_index: task.len(),
+            editing_task: None,
+            history: History::default(),
+            task_query: String::new(),
+            search_origin: None,
+            count: None,
+            pending_keys: Vec::new(),
+            popup_origin: InputMode::Normal,
+            popup_scroll: 0,
+            detail_toggled: false,
+            detail_scroll: 0,
+            detail_task: None,
+            command_input: String::new(),
+            selected_command: 0,
+            palette_origin: InputMode::Normal,
+            confirmation: None,
+            messages: Vec::new(),
             input: String::new(),
+            cursor: 0,
             input_mode: InputMode::Normal,
             should_exit: false,
+            save_failed: false,
+            keymap,
             config,
+            themes: BUILTIN_THEMES.iter().filter_map(|name| Theme::builtin(name)).collect(),
+            theme: Theme::default(),
+            color_support,
+        };
+
+        let theme = app.config.settings.theme.clone();
+        if !app.select_theme(&theme) {
+            app.select_theme("neon");
+        }
+        for shadow in shadows {
+            app.warn(shadow);
+        }
+        app
+    }
+
+    /// Adds the user's themes, replacing built-in themes of the same name, and switches to the configured
+    /// theme, warning if there is none of that name.
+    pub fn add_themes(&mut self, themes: Vec<Theme>) {
+        let selected = self.config.settings.theme.clone();
+
+        for theme in themes {
+            match self.themes.iter_mut().find(|existing| existing.name == theme.name) {
+                Some(existing) => *existing = theme,
+                None => self.themes.push(theme),
+            }
+        }
+
+        if !self.select_theme(&selected) {
+            self.warn(format!("Unknown theme \"{}\", using {}", selected, self.theme.name));
         }
     }
 
-    /// Returns the default style for the UI based on application configuration.
+    /// Switches to the theme called `name`, returning whether there is one.
+    pub fn select_theme(&mut self, name: &str) -> bool {
+        let Some(theme) = self.themes.iter().find(|theme| theme.name == name) else {
+            return false;
+        };
+
+        self.theme = theme.clone();
+        self.config.colors.apply(&mut self.theme);
+        self.theme.downgrade(self.color_support);
+        true
+    }
+
+    /// Reports `text` in the status line and adds it to the message history.
+    pub fn notify(&mut self, level: MessageLevel, text: impl Into<String>) {
+        self.messages.push(Message {
+            text: text.into(),
+            level,
+            created_at: unix_timestamp(),
+        });
+
+        if self.messages.len() > MESSAGE_HISTORY_LIMIT {
+            self.messages.drain(..self.messages.len() - MESSAGE_HISTORY_LIMIT);
+        }
+    }
+
+    /// Reports feedback about an action that worked.
+    pub fn info(&mut self, text: impl Into<String>) {
+        self.notify(MessageLevel::Info, text);
+    }
+
+    /// Reports an action that did nothing or something the user should know about.
+    pub fn warn(&mut self, text: impl Into<String>) {
+        self.notify(MessageLevel::Warning, text);
+    }
+
+    /// Reports an action that failed.
+    pub fn error(&mut self, text: impl Into<String>) {
+        self.notify(MessageLevel::Error, text);
+    }
+
+    /// Returns the message to show in the status line: the latest one, until it is older than the configured
+    /// number of seconds.
+    pub fn current_message(&self) -> Option<&Message> {
+        let seconds = self.config.settings.message_seconds;
+        self.messages
+            .last()
+            .filter(|message| seconds == 0 || unix_timestamp().saturating_sub(message.created_at) < seconds)
+    }
+
+    /// Captures the current task list and selection for the undo history, along with the trash or archive if
+    /// `scope` includes them.
+    pub fn snapshot(&self, description: &str, scope: Scope) -> Snapshot {
+        Snapshot {
+            description: description.to_string(),
+            task: self.task.clone(),
+            selected_task: self.selected_task,
+            trash: (scope == Scope::Trash).then(|| self.trash.clone()),
+            archive: (scope == Scope::Archive).then(|| self.archive.clone()),
+        }
+    }
+
+    /// Records the current task list in the undo history before a mutation described by `description`.
+    pub fn record(&mut self, description: &str) {
+        self.record_scope(description, Scope::Tasks);
+    }
+
+    /// Records the current state of what `scope` includes in the undo history before a mutation described by
+    /// `description`.
+    pub fn record_scope(&mut self, description: &str, scope: Scope) {
+        let snapshot = self.snapshot(description, scope);
+        self.history.record(snapshot, self.config.settings.history_limit);
+    }
+
+    /// Replaces the task list and selection, and the trash or archive if it holds them, with the contents of a
+    /// snapshot.
+    pub fn restore(&mut self, snapshot: Snapshot) {
+        self.task = snapshot.task;
+        self.selected_task = snapshot.selected_task.filter(|&index| index < self.task.len());
+        if let Some(trash) = snapshot.trash {
+            self.trash = trash;
+            self.selected_trash = match self.selected_trash {
+                _ if self.trash.is_empty() => None,
+                Some(index) => Some(index.min(self.trash.len() - 1)),
+                None => Some(0),
+            };
+        }
+        if let Some(archive) = snapshot.archive {
+            self.archive = archive;
+            self.selected_archive = None;
+        }
+    }
+
+    /// Returns the mode whose view is on screen. While help or the command palette is open that is the mode it
+    /// was opened from.
+    pub fn view_mode(&self) -> InputMode {
+        match self.input_mode {
+            InputMode::Help | InputMode::Messages => self.popup_origin,
+            InputMode::Palette => self.palette_origin,
+            InputMode::Confirm => self
+                .confirmation
+                .as_ref()
+                .map_or(InputMode::Normal, |confirmation| confirmation.origin),
+            mode => mode,
+        }
+    }
+
+    /// Returns the range of task indices between the visual mode anchor and the selection, while in visual mode.
+    pub fn visual_range(&self) -> Option<std::ops::RangeInclusive<usize>> {
+        match (self.input_mode, self.visual_anchor, self.selected_task) {
+            (InputMode::Visual, Some(anchor), Some(selected)) => Some(anchor.min(selected)..=anchor.max(selected)),
+            _ => None,
+        }
+    }
+
+    /// Returns how many tasks an action on the target tasks would affect: the marked tasks and the visual range,
+    /// or else the selected task.
+    pub fn target_count(&self) -> usize {
+        match (0..self.task.len()).filter(|&index| self.is_marked(index)).count() {
+            0 => self.selected_task.filter(|&index| index < self.task.len()).map_or(0, |_| 1),
+            marked => marked,
+        }
+    }
+
+    /// Returns whether the task at `index` is marked, or lies in the visual range.
+    pub fn is_marked(&self, index: usize) -> bool {
+        self.task.get(index).is_some_and(|task| task.marked)
+            || self.visual_range().is_some_and(|range| range.contains(&index))
+    }
+
+    /// Returns the text of the input field being typed into in the current mode, if any.
+    pub fn active_input(&self) -> Option<&str> {
+        match self.input_mode {
+            InputMode::Adding | InputMode::Tagging => Some(&self.input),
+            InputMode::ArchiveSearch => Some(&self.archive_query),
+            InputMode::Search => Some(&self.task_query),
+            InputMode::Palette => Some(&self.command_input),
+            _ => None,
+        }
+    }
+
+    /// Returns the input field being typed into in the current mode, if any.
+    pub fn active_input_mut(&mut self) -> Option<&mut String> {
+        match self.input_mode {
+            InputMode::Adding | InputMode::Tagging => Some(&mut self.input),
+            InputMode::ArchiveSearch => Some(&mut self.archive_query),
+            InputMode::Search => Some(&mut self.task_query),
+            InputMode::Palette => Some(&mut self.command_input),
+            _ => None,
+        }
+    }
+
+    /// Returns the indices of the tasks whose title contains the task search query, case-insensitively.
+    pub fn search_matches(&self) -> Vec<usize> {
+        let query = self.task_query.to_lowercase();
+        if query.is_empty() {
+            return Vec::new();
+        }
+
+        (0..self.task.len())
+            .filter(|&index| self.task[index].title.to_lowercase().contains(&query))
+            .collect()
+    }
+
+    /// Returns the search query the task list counts are limited to: the one being typed, if any. The query kept
+    /// for `n`/`N` after the search is finished does not limit them, as the list still shows every task.
+    pub fn counted_query(&self) -> Option<&str> {
+        (self.input_mode == InputMode::Search && !self.task_query.is_empty()).then_some(self.task_query.as_str())
+    }
+
+    /// Returns how many of the counted tasks are completed, and how many tasks are counted. While a search query is
+    /// being typed only the tasks matching it are counted.
+    pub fn progress(&self) -> (usize, usize) {
+        let counted: Vec<&Task> = match self.counted_query() {
+            None => self.task.iter().collect(),
+            Some(_) => self.search_matches().into_iter().map(|index| &self.task[index]).collect(),
+        };
+
+        (counted.iter().filter(|task| task.completed).count(), counted.len())
+    }
+
+    /// Returns the archived tasks matching the archive search query, case-insensitively, most recently archived first.
+    pub fn archive_results(&self) -> Vec<&ArchivedTask> {
+        let query = self.archive_query.to_lowercase();
+
+        self.archive
+            .iter()
+            .rev()
+            .filter(|archived| archived.task.title.to_lowercase().contains(&query))
+            .collect()
+    }
+
+    /// Returns the default style for the UI, taken from the active theme.
     pub fn default_style(&self) -> Style {
-        Style::default()
-            .fg(self.config.colors.foreground)
-            .bg(self.config.colors.background)
+        self.theme.text.style()
+    }
+
+    /// Returns the style of a status line message of the given level.
+    pub fn message_style(&self, level: MessageLevel) -> Style {
+        let element = match level {
+            MessageLevel::Info => &self.theme.info,
+            MessageLevel::Warning => &self.theme.warning,
+            MessageLevel::Error => &self.theme.error,
+        };
+
+        self.default_style().patch(element.style())
     }
 
     /// Returns the style for selected items in the UI.
     pub fn selection_style(&self) -> Style {
-        self.default_style()
-            .fg(self.config.colors.selection_fg)
-            .bg(self.config.colors.selection_bg)
+        self.default_style().patch(self.theme.selection.style())
     }
 
-    /// Returns the style for the check sign, adjusted based on whether the task is selected.
-    pub fn check_sign_style(&self, selected: bool) -> Style {
+    /// Returns the style of a row, which is the selection style when it is selected.
+    fn row_style(&self, selected: bool) -> Style {
         if selected {
-            self.selection_style().fg(self.config.colors.check_sign)
+            self.selection_style()
         } else {
-            self.default_style().fg(self.config.colors.check_sign)
+            self.default_style()
         }
     }
 
-    /// Returns the style for completed tasks, applying a crossed-out modifier.
+    /// Returns the style for the check sign, adjusted based on whether the task is selected.
+    pub fn check_sign_style(&self, selected: bool) -> Style {
+        self.row_style(selected).patch(self.theme.check_sign.style())
+    }
+
+    /// Returns the style for the title of completed tasks.
     pub fn checked_task_style(&self, selected: bool) -> Style {
-        if selected {
-            self.selection_style().add_modifier(Modifier::CROSSED_OUT)
-        } else {
-            self.default_style().add_modifier(Modifier::CROSSED_OUT)
-        }
+        self.row_style(selected).patch(self.theme.completed.style())
+    }
+
+    /// Returns the style for the priority marks of a task.
+    pub fn priority_style(&self, selected: bool) -> Style {
+        self.row_style(selected).patch(self.theme.priority.style())
+    }
+
+    /// Returns the style for the tags of a task.
+    pub fn tag_style(&self, selected: bool) -> Style {
+        self.row_style(selected).patch(self.theme.tag.style())
     }
-}
\ No newline at end of file
+
+    /// Returns the style for secondary text such as ages and key lists.
+    pub fn muted_style(&self, selected: bool) -> Style {
+        self.row_style(selected).patch(self.theme.muted.style())
+    }
+
+    /// Returns the style for keys shown in the navigation hint, help and dialogs.
+    pub fn key_style(&self) -> Style {
+        self.default_style().patch(self.theme.key.style())
+    }
+
+    /// Returns the style for an input field while text is being typed into it.
+    pub fn active_input_style(&self) -> Style {
+        self.default_style().patch(self.theme.active_input.style())
+    }
+}
+/// Returns a task for each of `titles`, as fixtures for the unit tests.
+#[cfg(test)]
+pub(crate) fn test_tasks(titles: &[&str]) -> Vec<Task> {
+    titles.iter().map(|title| Task::new(title.to_string())).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn progress_counts_matches_only_while_searching() {
+        let mut app = App::new(&test_tasks(&["buy milk", "call bob", "buy bread"]), Config::default());
+        app.task[0].set_completed(true);
+
+        utils::start_task_search(&mut app);
+        app.task_query = "buy".to_string();
+        assert_eq!(app.progress(), (1, 2));
+        assert_eq!(app.counted_query(), Some("buy"));
+
+        utils::finish_task_search(&mut app);
+        assert_eq!(app.progress(), (1, 3));
+        assert_eq!(app.counted_query(), None);
+    }
+}
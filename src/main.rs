@@ -1,44 +1,74 @@
 ///----------main.rs----------///
 
 use crossterm::{
-    event::{read, Event},
+    event::{poll, read, DisableMouseCapture, EnableMouseCapture, Event},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use std::{error::Error, io::stdout};
-use tui::{backend::CrosstermBackend, Terminal};
+use std::{env, error::Error, io::stdout, process, time::Duration};
+use tui::{backend::CrosstermBackend, layout::Rect, widgets::{Clear, ListState}, Terminal};
 use todo_list::{
-    events::{handle_events, handle_input_cursor},
-    file_handler::{load_task, save_task},
-    widget, App, CrossTerminal, DynResult, TerminalFrame, config::Config
+    events::{handle_events, handle_input_cursor, handle_mouse},
+    cli,
+    config::{BorderStyle, InputPosition},
+    file_handler::{load_archive, load_config, load_history, load_task, load_themes, load_trash},
+    stats::Stats,
+    unix_timestamp, utils, widget, App, CrossTerminal, DynResult, InputMode, TerminalFrame,
 };
 
 fn main() -> DynResult {
-    // Initialize the terminal for UI display, enable raw mode, and switch to alternate screen.
-    let mut terminal = initialise_terminal()?;
+    // Any command line arguments run a one-off command instead of the interface.
+    let args: Vec<String> = env::args().skip(1).collect();
+    if !args.is_empty() {
+        if let Err(error) = cli::run(&args) {
+            eprintln!("{}", error);
+            process::exit(1);
+        }
+        return Ok(());
+    }
 
     // Load task data and configuration from files.
     let task = load_task()?;
-    let config = Config::default();
+    let loaded = load_config().and_then(|config| {
+        let trash = load_trash(config.settings.trash_retention_days)?;
+        Ok((config, load_themes()?, trash))
+    });
+    let (config, themes, trash) = match loaded {
+        Ok(loaded) => loaded,
+        Err(error) => {
+            eprintln!("{}", error);
+            process::exit(1);
+        }
+    };
     let mut app = App::new(&task, config);
+    app.add_themes(themes);
+    app.trash = trash;
+    app.archive = load_archive()?;
+    app.history = load_history();
+    app.history.truncate(app.config.settings.history_limit);
+    // Archived after the history is loaded, so that undoing it puts the tasks back instead of duplicating them.
+    utils::auto_archive(&mut app);
 
-    // Start the main UI loop for the application.
+    // Initialize the terminal for UI display, enable raw mode, and switch to alternate screen.
+    let mut terminal = initialise_terminal(app.config.settings.mouse)?;
+
+    // Start the main UI loop for the application. Everything is saved when the user exits it.
     draw_ui(&mut terminal, &mut app)?;
 
     // Cleanup the terminal settings and return to normal terminal after the app closes.
     cleanup_terminal(terminal)?;
 
-    // Save the task data back to the file.
-    save_task(&app.task)?;
-
     Ok(())
 }
 
-/// Initialises and returns a terminal object
-fn initialise_terminal() -> Result<CrossTerminal, Box<dyn Error>> {
+/// Initialises and returns a terminal object, capturing the mouse if `mouse` is set
+fn initialise_terminal(mouse: bool) -> Result<CrossTerminal, Box<dyn Error>> {
     enable_raw_mode()?;
     let mut stdout = stdout();
     execute!(stdout, EnterAlternateScreen)?;
+    if mouse {
+        execute!(stdout, EnableMouseCapture)?;
+    }
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
     terminal.clear()?;
@@ -46,10 +76,10 @@ fn initialise_terminal() -> Result<CrossTerminal, Box<dyn Error>> {
     Ok(terminal)
 }
 
-/// Cleans up the terminal by disabling raw mode and exiting the alternate screen.
+/// Cleans up the terminal by disabling raw mode, releasing the mouse and exiting the alternate screen.
 fn cleanup_terminal(mut terminal: CrossTerminal) -> DynResult {
     disable_raw_mode()?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    execute!(terminal.backend_mut(), DisableMouseCapture, LeaveAlternateScreen)?;
 
     Ok(())
 }
@@ -57,14 +87,31 @@ fn cleanup_terminal(mut terminal: CrossTerminal) -> DynResult {
 /// Manages the main UI loop, rendering the UI and handling input events until the application should exit.
 fn draw_ui(terminal: &mut CrossTerminal, app: &mut App) -> DynResult {
     while !app.should_exit {
+        // Scroll the task list to the selection before drawing, using the height the list will be drawn with.
+        let layout = widget::main_chunks(app, terminal.size()?);
+        let heights = widget::task_heights(app, layout.list.width);
+        utils::scroll_to_selection(app, &heights, layout.list.height.saturating_sub(2) as usize);
+        if let Some(detail) = layout.detail {
+            let rows = widget::task_detail_rows(app, detail.width).len();
+            utils::clamp_detail_scroll(app, rows, detail.height.saturating_sub(2) as usize);
+        }
+
         // Draw the UI in the terminal using the provided application state.
         terminal.draw(|f| {
             app_view(f, app);
         })?;
 
-        // Read and handle user key events.
-        if let Ok(Event::Key(event)) = read() {
-            handle_events(event, app);
+        // Wait a moment for user key and mouse events, redrawing without one so that old messages expire.
+        // Clicks are hit-tested against the sections just drawn.
+        if !poll(Duration::from_millis(500))? {
+            continue;
+        }
+        match read() {
+            Ok(Event::Key(event)) => handle_events(event, app),
+            Ok(Event::Mouse(event)) => handle_mouse(event, app, &layout),
+            // Lay everything out again for the new size, starting from a blank screen.
+            Ok(Event::Resize(width, height)) => terminal.resize(Rect::new(0, 0, width, height))?,
+            _ => {}
         }
     }
 
@@ -73,19 +120,113 @@ fn draw_ui(terminal: &mut CrossTerminal, app: &mut App) -> DynResult {
 
 /// Renders the current view of the application in the terminal frame.
 fn app_view(frame: &mut TerminalFrame, app: &App) {
-    let main_chunks = widget::main_chunks(frame.size());
+    let layout = widget::main_chunks(app, frame.size());
+
+    // Render the task list, or the trash, archive or statistics when they are being looked at, in the main section.
+    match app.view_mode() {
+        // The trash and archive are scrolled to keep the selected task in view.
+        InputMode::Trash => {
+            let mut state = ListState::default();
+            state.select(app.selected_trash);
+            frame.render_stateful_widget(widget::trash_list(app), layout.list, &mut state);
+            ascii_borders(frame, app, layout.list);
+        }
+        InputMode::Archive | InputMode::ArchiveSearch => {
+            let mut state = ListState::default();
+            state.select(app.selected_archive);
+            frame.render_stateful_widget(widget::archive_list(app), layout.list, &mut state);
+            ascii_borders(frame, app, layout.list);
+        }
+        InputMode::Stats => {
+            let stats = Stats::new(app, app.config.settings.stats_weeks, unix_timestamp());
+            let counts = stats.counts();
+            let chunks = widget::stats_chunks(layout.list);
+            frame.render_widget(widget::completion_gauge(app, &stats, chunks[0].width), chunks[0]);
+            frame.render_widget(widget::task_counts(app, &counts), chunks[1]);
+            frame.render_widget(widget::stats_summary(app, &stats), chunks[2]);
+            let (days, columns) = widget::day_columns(&stats.per_day, chunks[3].width);
+            frame.render_widget(widget::completion_sparkline(app, &columns, days), chunks[3]);
+            for chunk in chunks {
+                ascii_borders(frame, app, chunk);
+            }
+        }
+        _ => {
+            frame.render_widget(widget::task_list(app, layout.list.width), layout.list);
+            ascii_borders(frame, app, layout.list);
+        }
+    }
 
-    // Render the task input widget in the first section and manage the input cursor.
-    let taskinput = widget::task_input(app);
-    frame.render_widget(taskinput, main_chunks[0]);
-    handle_input_cursor(&app, frame, &main_chunks);
+    // Render the completion gauge over the bottom border of the task list when it is switched on.
+    if let Some(progress) = layout.progress {
+        frame.render_widget(widget::progress_gauge(app), progress);
+    }
 
+    // Render the details of the selected task next to or below the list when they are shown.
+    if let Some(detail) = layout.detail {
+        frame.render_widget(widget::task_detail(app, detail.width), detail);
+        ascii_borders(frame, app, detail);
+    }
+
+    // Render the task input widget above, below or on top of the list and manage the input cursor.
+    if let Some(input) = layout.input {
+        if app.config.layout.input_position == InputPosition::Popup {
+            frame.render_widget(Clear, input);
+        }
+        frame.render_widget(widget::task_input(app), input);
+        ascii_borders(frame, app, input);
+        if app.input_mode != InputMode::Palette {
+            handle_input_cursor(app, frame, input);
+        }
+    }
+
+    // Render the navigation hint widget below the list, unless it is switched off.
+    if let Some(hint) = layout.hint {
+        frame.render_widget(widget::navigation_hint(app, hint.width, layout.compact), hint);
+    }
+
+    // Render the latest message in the status line at the bottom.
+    frame.render_widget(widget::status_line(app), layout.status);
 
-    // Render the task list widget in the second section.
-    let tasklist = widget::task_list(app);
-    frame.render_widget(tasklist, main_chunks[1]);
+    // Render the help overlay or the message history on top of everything else while it is open.
+    if matches!(app.input_mode, InputMode::Help | InputMode::Messages) {
+        let area = widget::centered_rect(70, 80, frame.size());
+        frame.render_widget(Clear, area);
+        match app.input_mode {
+            InputMode::Help => frame.render_widget(widget::help_popup(app), area),
+            _ => frame.render_widget(widget::messages_popup(app), area),
+        }
+        ascii_borders(frame, app, area);
+    }
+
+    // Render the confirmation dialog on top of the view the action was requested from.
+    if app.input_mode == InputMode::Confirm {
+        let (width, height) = widget::confirm_dialog_size(app);
+        let area = widget::fixed_rect(width, height, frame.size());
+        frame.render_widget(Clear, area);
+        frame.render_widget(widget::confirm_dialog(app), area);
+        ascii_borders(frame, app, area);
+    }
 
-    // Render the navigation hint widget in the third section.
-    let navigation_hint = widget::navigation_hint(app);
-    frame.render_widget(navigation_hint, main_chunks[2]);
+    // Render the command palette on top of the view it was opened from, keeping the selected command in view.
+    if app.input_mode == InputMode::Palette {
+        let area = widget::centered_rect(70, 60, frame.size());
+        let chunks = widget::palette_chunks(area);
+        let mut state = ListState::default();
+        state.select(Some(app.selected_command));
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(widget::palette_input(app), chunks[0]);
+        frame.render_stateful_widget(widget::palette_list(app), chunks[1], &mut state);
+        ascii_borders(frame, app, chunks[0]);
+        ascii_borders(frame, app, chunks[1]);
+        handle_input_cursor(app, frame, chunks[0]);
+    }
+}
+
+/// Swaps the box-drawing lines of the border around `area` for ASCII characters when the border style is ASCII.
+/// Called straight after drawing the bordered widget, before anything is drawn on top of it.
+fn ascii_borders(frame: &mut TerminalFrame, app: &App, area: Rect) {
+    if app.config.glyphs.border == BorderStyle::Ascii {
+        frame.render_widget(widget::AsciiBorders, area);
+    }
 }
\ No newline at end of file
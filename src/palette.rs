@@ -0,0 +1,145 @@
+///----------palette.rs----------///
+
+use crate::{action::Action, App};
+
+/// Actions that can be run from the command palette, in the order they are listed when nothing is typed.
+const ACTIONS: [Action; 35] = [
+    Action::NewTask,
+    Action::NewTaskAbove,
+    Action::NewTaskBelow,
+    Action::EditTask,
+    Action::CheckAndUncheckTask,
+    Action::DeleteTask,
+    Action::TagTask,
+    Action::CyclePriority,
+    Action::Search,
+    Action::SearchNext,
+    Action::SearchPrevious,
+    Action::ListUp,
+    Action::ListDown,
+    Action::ListTop,
+    Action::ListBottom,
+    Action::MoveTaskUp,
+    Action::MoveTaskDown,
+    Action::MoveTaskTop,
+    Action::MoveTaskBottom,
+    Action::ToggleMark,
+    Action::VisualMode,
+    Action::ClearMarks,
+    Action::Undo,
+    Action::Redo,
+    Action::ArchiveCompleted,
+    Action::ViewArchive,
+    Action::ViewStats,
+    Action::ViewTrash,
+    Action::EmptyTrash,
+    Action::SaveToDisk,
+    Action::Help,
+    Action::MessageHistory,
+    Action::CycleTheme,
+    Action::ToggleDetails,
+    Action::ExitApp,
+];
+
+/// Fields the task list can be sorted by with the `sort` command.
+pub const SORT_FIELDS: [&str; 5] = ["title", "priority", "created", "due", "status"];
+
+/// An entry of the command palette.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Command {
+    /// Runs an action, the same as pressing its keys. `tag_task`, `search` and `cycle_theme` accept an argument.
+    Action(Action),
+    /// Sorts the task list by the field given as argument.
+    Sort,
+    /// Sets the notes of the selected task to the argument, or clears them without one.
+    Note,
+    /// Sets the due date of the selected task to the `YYYY-MM-DD` date given as argument, or clears it without one.
+    Due,
+}
+
+impl Command {
+    /// Returns the name typed to run the command, which for actions is the name of their key binding.
+    pub fn name(self) -> String {
+        match self {
+            Command::Action(action) => action.name(),
+            Command::Sort => "sort".to_string(),
+            Command::Note => "note".to_string(),
+            Command::Due => "due".to_string(),
+        }
+    }
+
+    /// Returns a short description of the command, including the argument it takes, if any.
+    pub fn description(self) -> String {
+        match self {
+            Command::Action(Action::TagTask) => "Add/remove tag [tag]".to_string(),
+            Command::Action(Action::Search) => "Search [text]".to_string(),
+            Command::Action(Action::CycleTheme) => "Switch to the next theme [name]".to_string(),
+            Command::Action(action) => action.description().to_string(),
+            Command::Sort => format!("Sort tasks by {}", SORT_FIELDS.join("/")),
+            Command::Note => "Set the notes of the selected task [text]".to_string(),
+            Command::Due => "Set the due date of the selected task [YYYY-MM-DD]".to_string(),
+        }
+    }
+}
+
+/// Returns every command of the palette.
+pub fn commands() -> Vec<Command> {
+    ACTIONS.iter().map(|&action| Command::Action(action)).chain([Command::Sort, Command::Note, Command::Due]).collect()
+}
+
+/// Splits the palette input into the command name being typed and its argument, if any.
+pub fn split_input(input: &str) -> (&str, Option<&str>) {
+    let input = input.trim_start();
+    match input.split_once(char::is_whitespace) {
+        Some((name, argument)) if !argument.trim().is_empty() => (name, Some(argument.trim())),
+        Some((name, _)) => (name, None),
+        None => (input, None),
+    }
+}
+
+/// Returns the commands matching the name typed in the palette, best match first.
+pub fn matching_commands(app: &App) -> Vec<Command> {
+    let (query, _) = split_input(&app.command_input);
+
+    let mut scored: Vec<(i64, Command)> = commands()
+        .into_iter()
+        .filter_map(|command| {
+            // Matches on the name count for more than matches on the description.
+            let name_score = fuzzy_score(query, &command.name()).map(|score| score + 3);
+            let score = name_score.max(fuzzy_score(query, &command.description()));
+            score.map(|score| (score, command))
+        })
+        .collect();
+
+    // The sort is stable, so equally good matches keep the palette order.
+    scored.sort_by(|(a, _), (b, _)| b.cmp(a));
+    scored.into_iter().map(|(_, command)| command).collect()
+}
+
+/// Scores how well `query` matches `candidate` when its characters appear in order, case-insensitively, though
+/// not necessarily next to each other. Consecutive characters and characters at the start of a word score
+/// higher. Returns nothing if `candidate` does not contain the query.
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i64> {
+    let candidate: Vec<char> = candidate.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous: Option<usize> = None;
+
+    for wanted in query.to_lowercase().chars() {
+        let found = position + candidate[position..].iter().position(|&c| c == wanted)?;
+
+        score += 1;
+        if previous.is_some_and(|previous| previous + 1 == found) {
+            score += 5;
+        }
+        if found == 0 || matches!(candidate[found - 1], '_' | ' ' | '/') {
+            score += 8;
+        }
+        score -= (found - position) as i64;
+
+        previous = Some(found);
+        position = found + 1;
+    }
+
+    Some(score)
+}
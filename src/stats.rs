@@ -0,0 +1,63 @@
+///----------stats.rs----------///
+
+use crate::App;
+
+/// Seconds in a day, the unit completions are counted in.
+const DAY: u64 = 86400;
+
+/// Figures shown on the statistics screen, computed from the task list and the archive.
+pub struct Stats {
+    pub open: u64,                    // Tasks in the list that are not completed.
+    pub completed: u64,               // Completed tasks still in the list.
+    pub archived: u64,                // Tasks moved to the archive, all of them completed.
+    pub per_day: Vec<u64>,            // Tasks completed on each of the last days, oldest first and today last.
+    pub average_seconds: Option<u64>, // Average time from adding a task to completing it, if any task has both.
+}
+
+impl Stats {
+    /// Computes the statistics of the tasks in `app`, charting completions over the `weeks` weeks up to `now`.
+    /// Days start at midnight UTC, like the dates in the task details.
+    pub fn new(app: &App, weeks: usize, now: u64) -> Self {
+        let days = weeks.max(1) * 7;
+        let today = now / DAY;
+        let mut per_day = vec![0; days];
+        let mut durations = Vec::new();
+
+        let archived = app.archive.iter().map(|archived| &archived.task);
+        for task in app.task.iter().chain(archived).filter(|task| task.completed) {
+            let Some(completed_at) = task.completed_at else {
+                continue;
+            };
+            let age = today.saturating_sub(completed_at / DAY) as usize;
+            if age < days {
+                per_day[days - 1 - age] += 1;
+            }
+            if let Some(created_at) = task.created_at {
+                durations.push(completed_at.saturating_sub(created_at));
+            }
+        }
+
+        let completed = app.task.iter().filter(|task| task.completed).count() as u64;
+        Self {
+            open: app.task.len() as u64 - completed,
+            completed,
+            archived: app.archive.len() as u64,
+            per_day,
+            average_seconds: (!durations.is_empty())
+                .then(|| durations.iter().sum::<u64>() / durations.len() as u64),
+        }
+    }
+
+    /// Returns the bars of the task count chart.
+    pub fn counts(&self) -> [(&'static str, u64); 3] {
+        [("Open", self.open), ("Done", self.completed), ("Archived", self.archived)]
+    }
+
+    /// Returns the share of the tasks in the list that are completed, from 0 to 1.
+    pub fn done_ratio(&self) -> f64 {
+        match self.open + self.completed {
+            0 => 0.0,
+            total => self.completed as f64 / total as f64,
+        }
+    }
+}
@@ -0,0 +1,287 @@
+///----------theme.rs----------///
+
+use crate::{color, config::ColorSupport};
+use serde::{Deserialize, Serialize};
+use tui::style::{Color, Modifier, Style};
+use StyleModifier::{Bold, CrossedOut, Dim, Reversed, Underlined};
+
+/// Names of the built-in themes, in the order they are cycled through.
+pub const BUILTIN_THEMES: [&str; 5] = ["neon", "solarized-dark", "solarized-light", "gruvbox", "high-contrast"];
+
+/// Text attributes that can be added to the style of an element.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum StyleModifier {
+    Bold,
+    Dim,
+    Italic,
+    Underlined,
+    SlowBlink,
+    RapidBlink,
+    Reversed,
+    Hidden,
+    CrossedOut,
+}
+
+impl StyleModifier {
+    /// Returns the matching tui modifier.
+    fn modifier(self) -> Modifier {
+        match self {
+            StyleModifier::Bold => Modifier::BOLD,
+            StyleModifier::Dim => Modifier::DIM,
+            StyleModifier::Italic => Modifier::ITALIC,
+            StyleModifier::Underlined => Modifier::UNDERLINED,
+            StyleModifier::SlowBlink => Modifier::SLOW_BLINK,
+            StyleModifier::RapidBlink => Modifier::RAPID_BLINK,
+            StyleModifier::Reversed => Modifier::REVERSED,
+            StyleModifier::Hidden => Modifier::HIDDEN,
+            StyleModifier::CrossedOut => Modifier::CROSSED_OUT,
+        }
+    }
+}
+
+/// Style of one element of the interface. Colors left out are inherited from the style the element is drawn on.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, Default)]
+#[serde(default)]
+pub struct ElementStyle {
+    #[serde(with = "color::optional", skip_serializing_if = "Option::is_none")]
+    pub fg: Option<Color>, // Text color.
+    #[serde(with = "color::optional", skip_serializing_if = "Option::is_none")]
+    pub bg: Option<Color>, // Background color.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub modifiers: Vec<StyleModifier>, // Attributes such as bold or italic.
+}
+
+impl ElementStyle {
+    /// Constructor for an element style with the given colors and modifiers.
+    pub fn new(fg: Option<Color>, bg: Option<Color>, modifiers: &[StyleModifier]) -> Self {
+        Self {
+            fg,
+            bg,
+            modifiers: modifiers.to_vec(),
+        }
+    }
+
+    /// Returns the tui style, which only sets what the element sets.
+    pub fn style(&self) -> Style {
+        let mut style = Style::default();
+        if let Some(fg) = self.fg {
+            style = style.fg(fg);
+        }
+        if let Some(bg) = self.bg {
+            style = style.bg(bg);
+        }
+        self.modifiers
+            .iter()
+            .fold(style, |style, modifier| style.add_modifier(modifier.modifier()))
+    }
+
+    /// Adds `modifiers` that the element does not have yet.
+    fn add_modifiers(&mut self, modifiers: &[StyleModifier]) {
+        for modifier in modifiers {
+            if !self.modifiers.contains(modifier) {
+                self.modifiers.push(*modifier);
+            }
+        }
+    }
+}
+
+/// The styles of every element of the interface. A theme file only needs the elements it changes; the others
+/// keep the neon theme's styles.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct Theme {
+    #[serde(skip)]
+    pub name: String, // Name of the built-in theme, or of the theme file without `.json`.
+    pub text: ElementStyle,         // Default text and background.
+    pub selection: ElementStyle,    // Selected row of a list.
+    pub check_sign: ElementStyle,   // Check marks and the marker of marked tasks.
+    pub completed: ElementStyle,    // Title of a completed task.
+    pub priority: ElementStyle,     // Priority marks in front of a title.
+    pub tag: ElementStyle,          // Tags after a title.
+    pub muted: ElementStyle,        // Secondary text such as ages in the trash and archive.
+    pub key: ElementStyle,          // Keys in the navigation hint, help and dialogs.
+    pub active_input: ElementStyle, // Input field while text is being typed into it.
+    pub info: ElementStyle,         // Feedback in the status line.
+    pub warning: ElementStyle,      // Warnings in the status line.
+    pub error: ElementStyle,        // Errors in the status line.
+}
+
+/// Provides the neon theme.
+impl Default for Theme {
+    fn default() -> Self {
+        Self::neon()
+    }
+}
+
+/// Shorthand for an element with a foreground color.
+fn fg(color: Color, modifiers: &[StyleModifier]) -> ElementStyle {
+    ElementStyle::new(Some(color), None, modifiers)
+}
+
+/// Shorthand for an element with a foreground and a background color.
+fn fg_bg(fg: Color, bg: Color, modifiers: &[StyleModifier]) -> ElementStyle {
+    ElementStyle::new(Some(fg), Some(bg), modifiers)
+}
+
+impl Theme {
+    /// Replaces colors the terminal cannot show by the nearest ones it can. Without any colors, attributes take
+    /// their place so that the selection, completed tasks, keys and the active input still stand out.
+    pub fn downgrade(&mut self, support: ColorSupport) {
+        for element in self.elements_mut() {
+            element.fg = element.fg.map(|fg| color::downgrade(fg, support));
+            element.bg = element.bg.map(|bg| color::downgrade(bg, support));
+        }
+
+        // Nearby colors can merge into one, which would hide the selection.
+        let text = self.text.style();
+        let selection = text.patch(self.selection.style());
+        if (selection.fg, selection.bg) == (text.fg, text.bg) {
+            self.selection.add_modifiers(&[Reversed]);
+        }
+
+        if support == ColorSupport::None {
+            self.completed.add_modifiers(&[CrossedOut, Dim]);
+            self.check_sign.add_modifiers(&[Bold]);
+            self.priority.add_modifiers(&[Bold]);
+            self.key.add_modifiers(&[Bold]);
+            self.active_input.add_modifiers(&[Underlined]);
+            self.warning.add_modifiers(&[Bold]);
+            self.error.add_modifiers(&[Bold, Underlined]);
+        }
+    }
+
+    /// Returns every element of the theme.
+    fn elements_mut(&mut self) -> [&mut ElementStyle; 12] {
+        [
+            &mut self.text,
+            &mut self.selection,
+            &mut self.check_sign,
+            &mut self.completed,
+            &mut self.priority,
+            &mut self.tag,
+            &mut self.muted,
+            &mut self.key,
+            &mut self.active_input,
+            &mut self.info,
+            &mut self.warning,
+            &mut self.error,
+        ]
+    }
+
+    /// Returns the built-in theme called `name`, if there is one.
+    pub fn builtin(name: &str) -> Option<Self> {
+        let theme = match name {
+            "neon" => Self::neon(),
+            "solarized-dark" => Self::solarized(true),
+            "solarized-light" => Self::solarized(false),
+            "gruvbox" => Self::gruvbox(),
+            "high-contrast" => Self::high_contrast(),
+            _ => return None,
+        };
+
+        Some(Self {
+            name: name.to_string(),
+            ..theme
+        })
+    }
+
+    /// Pink on black with teal selection, the original look of the application.
+    fn neon() -> Self {
+        let yellow = Color::Rgb(217, 200, 25);
+
+        Self {
+            name: "neon".to_string(),
+            text: fg_bg(Color::Rgb(242, 60, 147), Color::Rgb(0, 0, 0), &[]),
+            selection: fg_bg(Color::Rgb(255, 255, 255), Color::Rgb(0, 128, 128), &[]),
+            check_sign: fg(yellow, &[]),
+            completed: ElementStyle::new(None, None, &[CrossedOut]),
+            priority: fg(yellow, &[Bold]),
+            tag: ElementStyle::new(None, None, &[Dim]),
+            muted: ElementStyle::new(None, None, &[Dim]),
+            key: ElementStyle::new(None, None, &[Bold]),
+            active_input: fg(Color::Rgb(0, 128, 128), &[]),
+            info: fg(Color::Rgb(255, 255, 255), &[]),
+            warning: fg(yellow, &[Bold]),
+            error: fg(Color::Rgb(255, 85, 85), &[Bold]),
+        }
+    }
+
+    /// Ethan Schoonover's Solarized palette, on its dark or light background.
+    fn solarized(dark: bool) -> Self {
+        let (background, highlight, body, emphasis, comment) = if dark {
+            (
+                Color::Rgb(0, 43, 54),
+                Color::Rgb(7, 54, 66),
+                Color::Rgb(131, 148, 150),
+                Color::Rgb(147, 161, 161),
+                Color::Rgb(88, 110, 117),
+            )
+        } else {
+            (
+                Color::Rgb(253, 246, 227),
+                Color::Rgb(238, 232, 213),
+                Color::Rgb(101, 123, 131),
+                Color::Rgb(88, 110, 117),
+                Color::Rgb(147, 161, 161),
+            )
+        };
+
+        Self {
+            name: String::new(),
+            text: fg_bg(body, background, &[]),
+            selection: fg_bg(emphasis, highlight, &[Bold]),
+            check_sign: fg(Color::Rgb(133, 153, 0), &[]),
+            completed: fg(comment, &[CrossedOut]),
+            priority: fg(Color::Rgb(203, 75, 22), &[Bold]),
+            tag: fg(Color::Rgb(42, 161, 152), &[]),
+            muted: fg(comment, &[]),
+            key: fg(Color::Rgb(38, 139, 210), &[Bold]),
+            active_input: fg(Color::Rgb(38, 139, 210), &[]),
+            info: fg(emphasis, &[]),
+            warning: fg(Color::Rgb(181, 137, 0), &[Bold]),
+            error: fg(Color::Rgb(220, 50, 47), &[Bold]),
+        }
+    }
+
+    /// The warm, retro gruvbox dark palette.
+    fn gruvbox() -> Self {
+        let gray = Color::Rgb(146, 131, 116);
+        let yellow = Color::Rgb(250, 189, 47);
+
+        Self {
+            name: String::new(),
+            text: fg_bg(Color::Rgb(235, 219, 178), Color::Rgb(40, 40, 40), &[]),
+            selection: fg_bg(Color::Rgb(251, 241, 199), Color::Rgb(80, 73, 69), &[Bold]),
+            check_sign: fg(Color::Rgb(184, 187, 38), &[]),
+            completed: fg(gray, &[CrossedOut]),
+            priority: fg(Color::Rgb(254, 128, 25), &[Bold]),
+            tag: fg(Color::Rgb(142, 192, 124), &[]),
+            muted: fg(gray, &[]),
+            key: fg(yellow, &[Bold]),
+            active_input: fg(Color::Rgb(131, 165, 152), &[]),
+            info: fg(Color::Rgb(235, 219, 178), &[]),
+            warning: fg(yellow, &[Bold]),
+            error: fg(Color::Rgb(251, 73, 52), &[Bold]),
+        }
+    }
+
+    /// White on black with a yellow selection, using only the basic terminal colors.
+    fn high_contrast() -> Self {
+        Self {
+            name: String::new(),
+            text: fg_bg(Color::White, Color::Black, &[]),
+            selection: fg_bg(Color::Black, Color::Yellow, &[Bold]),
+            check_sign: fg(Color::LightGreen, &[Bold]),
+            completed: fg(Color::Gray, &[CrossedOut]),
+            priority: fg(Color::LightRed, &[Bold]),
+            tag: fg(Color::LightCyan, &[]),
+            muted: fg(Color::Gray, &[]),
+            key: fg(Color::Yellow, &[Bold, Underlined]),
+            active_input: fg(Color::Yellow, &[Bold]),
+            info: fg(Color::White, &[]),
+            warning: fg(Color::Yellow, &[Bold]),
+            error: fg(Color::LightRed, &[Bold]),
+        }
+    }
+}
@@ -1,16 +1,66 @@
 ///----------utils.rs----------///
 
-use crate::{App, InputMode, Task};
+use crate::{
+    action::{self, Action, Confirmation},
+    config::InsertPosition,
+    file_handler,
+    history::{Scope, Snapshot},
+    palette::{self, Command},
+    unix_timestamp, App, ArchivedTask, InputMode, Priority, Task, TrashedTask,
+};
+use std::cmp::Ordering;
 
-/// Sets the app to add a new task, changing the input mode to `Adding` and deselecting any currently selected task.
+/// Sets the app to add a new task, changing the input mode to `Adding` and working out where saved tasks will be inserted.
 pub fn new_task(app: &mut App) {
     app.input_mode = InputMode::Adding;
-    app.selected_task = None;
+    input_end(app);
+    app.insert_index = match (app.config.settings.insert_position, app.selected_task) {
+        (InsertPosition::Top, _) => 0,
+        (InsertPosition::AboveSelection, Some(index)) => index,
+        (InsertPosition::BelowSelection, Some(index)) => index + 1,
+        _ => app.task.len(),
+    }
+    .min(app.task.len());
+}
+
+/// Starts adding new tasks directly above the selected task, whatever the configured insert position.
+pub fn new_task_above(app: &mut App) {
+    new_task(app);
+    app.insert_index = app.selected_task.unwrap_or(0).min(app.task.len());
+}
+
+/// Starts adding new tasks directly below the selected task, whatever the configured insert position.
+pub fn new_task_below(app: &mut App) {
+    new_task(app);
+    app.insert_index = app.selected_task.map_or(app.task.len(), |index| index + 1).min(app.task.len());
 }
 
-/// Sets the flag to exit the application.
+/// Saves everything and sets the flag to exit the application. If saving fails the error is reported and the
+/// application keeps running, unless saving already failed on an earlier attempt to exit.
 pub fn exit_app(app: &mut App) {
-    app.input_mode = InputMode::ConfirmQuit
+    match file_handler::save_all(app) {
+        Ok(()) => app.should_exit = true,
+        Err(_) if app.save_failed => app.should_exit = true,
+        Err(error) => {
+            app.save_failed = true;
+            app.error(format!("Could not save on exit: {}. Quit again to exit without saving", error));
+        }
+    }
+}
+
+/// Holds back an action and asks the user to confirm it.
+pub fn ask_confirmation(app: &mut App, confirmation: Confirmation) {
+    app.confirmation = Some(confirmation);
+    app.input_mode = InputMode::Confirm;
+}
+
+/// Drops the action waiting for confirmation and returns to the mode it was requested in.
+pub fn decline(app: &mut App) {
+    if let Some(confirmation) = app.confirmation.take() {
+        app.input_mode = confirmation.origin;
+    } else {
+        app.input_mode = InputMode::Normal;
+    }
 }
 
 /// Moves the selection up in the task list, if possible.
@@ -25,49 +75,1061 @@ pub fn list_up(app: &mut App) {
 /// Moves the selection down in the task list, if possible.
 pub fn list_down(app: &mut App) {
     if let Some(index) = app.selected_task {
-        if index < app.task.len() - 1 {
+        if index + 1 < app.task.len() {
             app.selected_task = Some(index + 1);
         }
     }
 }
 
-/// Toggles the completion status of the currently selected task.
+/// Selects the first task in the list.
+pub fn list_top(app: &mut App) {
+    if !app.task.is_empty() {
+        app.selected_task = Some(0);
+    }
+}
+
+/// Selects the last task in the list.
+pub fn list_bottom(app: &mut App) {
+    app.selected_task = app.task.len().checked_sub(1);
+}
+
+/// Selects task number `number`, counting from 1, or the last task if there are fewer.
+pub fn go_to_task(app: &mut App, number: usize) {
+    if !app.task.is_empty() {
+        app.selected_task = Some(number.saturating_sub(1).min(app.task.len() - 1));
+    }
+}
+
+/// Scrolls the task list just enough for the selected task to be visible in a list `height` rows high, where
+/// `heights` holds the number of rows each task takes up.
+pub fn scroll_to_selection(app: &mut App, heights: &[usize], height: usize) {
+    let rows = |tasks: &[usize]| tasks.iter().sum::<usize>();
+
+    if let Some(selected) = app.selected_task.filter(|&selected| selected < heights.len()) {
+        if selected < app.task_offset {
+            app.task_offset = selected;
+        }
+        while app.task_offset < selected && rows(&heights[app.task_offset..=selected]) > height {
+            app.task_offset += 1;
+        }
+    }
+
+    // Do not leave empty rows at the bottom while there are tasks above the top.
+    app.task_offset = app.task_offset.min(heights.len());
+    while app.task_offset > 0 && rows(&heights[app.task_offset - 1..]) <= height {
+        app.task_offset -= 1;
+    }
+}
+
+/// Shows the task details pane if it is hidden, or hides it if it is shown.
+pub fn toggle_details(app: &mut App) {
+    app.detail_toggled = !app.detail_toggled;
+}
+
+/// Scrolls the task details pane by `lines`, negative to scroll up. The pane keeps the scroll within its
+/// contents when it is drawn.
+pub fn scroll_details(app: &mut App, lines: isize) {
+    app.detail_scroll = app.detail_scroll.saturating_add_signed(lines);
+}
+
+/// Keeps the scroll of the task details pane within its `rows` of contents in a pane `height` rows high, and
+/// scrolls back to the top when another task is selected.
+pub fn clamp_detail_scroll(app: &mut App, rows: usize, height: usize) {
+    if app.detail_task != app.selected_task {
+        app.detail_task = app.selected_task;
+        app.detail_scroll = 0;
+    }
+    app.detail_scroll = app.detail_scroll.min(rows.saturating_sub(height));
+}
+
+/// Selects the task at `index`, as when it is clicked.
+pub fn select_task(app: &mut App, index: usize) {
+    if index < app.task.len() {
+        app.selected_task = Some(index);
+    }
+}
+
+/// Selects the task at `index` and checks or unchecks it on its own, regardless of any marked tasks.
+pub fn toggle_task(app: &mut App, index: usize) {
+    if index < app.task.len() {
+        app.selected_task = Some(index);
+        let completed = !app.task[index].completed;
+        app.record(if completed { "check task" } else { "uncheck task" });
+        app.task[index].set_completed(completed);
+    }
+}
+
+/// Starts a search of the task list, remembering the selection to go back to if the search is abandoned.
+pub fn start_task_search(app: &mut App) {
+    app.task_query.clear();
+    app.search_origin = app.selected_task;
+    app.input_mode = InputMode::Search;
+    input_start(app);
+}
+
+/// Stops typing the search query, keeping the matching task selected and the query for `search_next`.
+pub fn finish_task_search(app: &mut App) {
+    app.input_mode = InputMode::Normal;
+    if !app.task_query.is_empty() && app.search_matches().is_empty() {
+        app.warn(format!("No task matches \"{}\"", app.task_query));
+    }
+}
+
+/// Abandons the search, restoring the selection from before it started.
+pub fn cancel_task_search(app: &mut App) {
+    app.task_query.clear();
+    app.selected_task = app.search_origin;
+    app.input_mode = InputMode::Normal;
+}
+
+/// Selects the first task matching the query at or after the task selected when the search started.
+fn select_search_match(app: &mut App) {
+    let start = app.search_origin.unwrap_or(0);
+    let matches = app.search_matches();
+    app.selected_task = matches
+        .iter()
+        .find(|&&index| index >= start)
+        .or(matches.first())
+        .copied()
+        .or(app.search_origin);
+}
+
+/// Selects the next task matching the last search, wrapping around to the top.
+pub fn search_next(app: &mut App) {
+    let matches = app.search_matches();
+    let current = app.selected_task;
+    let next = matches
+        .iter()
+        .find(|&&index| Some(index) > current)
+        .or(matches.first());
+    select_match(app, next.copied());
+}
+
+/// Selects the previous task matching the last search, wrapping around to the bottom.
+pub fn search_previous(app: &mut App) {
+    let matches = app.search_matches();
+    let current = app.selected_task.unwrap_or(0);
+    let previous = matches
+        .iter()
+        .rev()
+        .find(|&&index| index < current)
+        .or(matches.last());
+    select_match(app, previous.copied());
+}
+
+/// Selects a search match, or reports that nothing matched.
+fn select_match(app: &mut App, index: Option<usize>) {
+    match index {
+        Some(index) => app.selected_task = Some(index),
+        None if app.task_query.is_empty() => app.warn("No previous search"),
+        None => app.warn(format!("No task matches \"{}\"", app.task_query)),
+    }
+}
+
+/// Returns the indices of the tasks an action applies to: the marked tasks if there are any, otherwise the selected task.
+/// Entering an action from visual mode first turns the visual range into marks.
+pub fn target_indices(app: &mut App) -> Vec<usize> {
+    commit_visual_range(app);
+
+    let marked: Vec<usize> = (0..app.task.len()).filter(|&index| app.task[index].marked).collect();
+    if !marked.is_empty() {
+        return marked;
+    }
+
+    app.selected_task.filter(|&index| index < app.task.len()).into_iter().collect()
+}
+
+/// Describes a mutation for the undo history, e.g. "delete task" or "delete 3 tasks".
+fn describe(verb: &str, count: usize, suffix: &str) -> String {
+    if count == 1 {
+        format!("{} task{}", verb, suffix)
+    } else {
+        format!("{} {} tasks{}", verb, count, suffix)
+    }
+}
+
+/// Toggles the completion status of the target tasks. When they differ, all of them are checked.
 pub fn check_and_uncheck_task(app: &mut App) {
-    if let Some(index) = app.selected_task {
-        app.task[index].completed = !app.task[index].completed;
+    let targets = target_indices(app);
+    if targets.is_empty() {
+        return;
+    }
+
+    let completed = !targets.iter().all(|&index| app.task[index].completed);
+    app.record(&describe(if completed { "check" } else { "uncheck" }, targets.len(), ""));
+    for index in targets {
+        app.task[index].set_completed(completed);
     }
 }
 
-/// Deletes the currently selected task from the list. Adjusts the selection if needed.
+/// Reorders the task list, keeping the selection on the same task. `reorder` receives which positions hold
+/// target tasks and rearranges the positions in place.
+fn move_targets(app: &mut App, suffix: &str, reorder: impl FnOnce(&mut Vec<usize>, &[bool])) {
+    let targets = target_indices(app);
+    if targets.is_empty() {
+        return;
+    }
+
+    let mut is_target = vec![false; app.task.len()];
+    for &index in &targets {
+        is_target[index] = true;
+    }
+
+    let mut order: Vec<usize> = (0..app.task.len()).collect();
+    reorder(&mut order, &is_target);
+    if order.iter().enumerate().all(|(position, &index)| position == index) {
+        return;
+    }
+
+    app.record(&describe("move", targets.len(), suffix));
+    let mut old_task: Vec<Option<Task>> = app.task.drain(..).map(Some).collect();
+    app.task = order.iter().filter_map(|&index| old_task[index].take()).collect();
+    app.selected_task = app
+        .selected_task
+        .and_then(|selected| order.iter().position(|&index| index == selected));
+}
+
+/// Moves the target tasks one position up, keeping the selection on the same task.
+pub fn move_task_up(app: &mut App) {
+    move_targets(app, " up", |order, is_target| {
+        for position in 1..order.len() {
+            if is_target[order[position]] && !is_target[order[position - 1]] {
+                order.swap(position, position - 1);
+            }
+        }
+    });
+}
+
+/// Moves the target tasks one position down, keeping the selection on the same task.
+pub fn move_task_down(app: &mut App) {
+    move_targets(app, " down", |order, is_target| {
+        for position in (1..order.len()).rev() {
+            if is_target[order[position - 1]] && !is_target[order[position]] {
+                order.swap(position, position - 1);
+            }
+        }
+    });
+}
+
+/// Moves the target tasks to the top of the list, keeping their relative order.
+pub fn move_task_top(app: &mut App) {
+    move_targets(app, " to top", |order, is_target| {
+        order.sort_by_key(|&index| !is_target[index]);
+    });
+}
+
+/// Moves the target tasks to the bottom of the list, keeping their relative order.
+pub fn move_task_bottom(app: &mut App) {
+    move_targets(app, " to bottom", |order, is_target| {
+        order.sort_by_key(|&index| is_target[index]);
+    });
+}
+
+/// Moves the target tasks from the list to the trash. Adjusts the selection if needed.
 pub fn delete_task(app: &mut App) {
-    if let Some(index) = app.selected_task {
-        app.task.remove(index);
-        if app.task.is_empty() {
-            app.selected_task = None;
-        } else if app.selected_task.unwrap() == app.task.len() {
-            app.selected_task = Some(app.task.len() - 1);
+    let targets = target_indices(app);
+    if targets.is_empty() {
+        return;
+    }
+
+    app.record_scope(&describe("delete", targets.len(), ""), Scope::Trash);
+    for &index in targets.iter().rev() {
+        let mut task = app.task.remove(index);
+        task.marked = false;
+        app.trash.push(TrashedTask::new(task));
+    }
+
+    app.selected_task = match app.selected_task {
+        _ if app.task.is_empty() => None,
+        Some(index) => Some(index.min(app.task.len() - 1)),
+        None => None,
+    };
+}
+
+/// Sets the priority of the target tasks to the one after the selected task's priority, cycling back to none.
+pub fn cycle_priority(app: &mut App) {
+    let targets = target_indices(app);
+    let current = app.selected_task.filter(|&index| index < app.task.len()).or(targets.first().copied());
+    let Some(current) = current else {
+        return;
+    };
+
+    let priority = Priority::next(app.task[current].priority);
+    let label = priority.map_or("none", Priority::label);
+    app.record(&describe("set priority of", targets.len(), &format!(" to {}", label)));
+    for index in targets {
+        app.task[index].priority = priority;
+    }
+}
+
+/// Starts typing a tag to add to or remove from the target tasks.
+pub fn tag_task(app: &mut App) {
+    if !target_indices(app).is_empty() {
+        app.input.clear();
+        app.input_mode = InputMode::Tagging;
+        input_start(app);
+    }
+}
+
+/// Adds the typed tag to the target tasks, or removes it when every target already has it.
+pub fn save_tag(app: &mut App) {
+    let tag: String = app.input.drain(..).collect();
+    app.cursor = 0;
+    app.input_mode = InputMode::Normal;
+    toggle_tag(app, &tag);
+}
+
+/// Adds `tag` to the target tasks, or removes it when every target already has it, then clears the marks.
+pub fn toggle_tag(app: &mut App, tag: &str) {
+    let tag = tag_name(tag).to_string();
+    let targets = target_indices(app);
+    if tag.is_empty() || targets.is_empty() {
+        return;
+    }
+
+    if tag_removes(app, &tag) {
+        app.record(&describe("untag", targets.len(), &format!(" #{}", tag)));
+        for index in targets {
+            app.task[index].tags.retain(|existing| existing != &tag);
+        }
+    } else {
+        app.record(&describe("tag", targets.len(), &format!(" #{}", tag)));
+        for index in targets {
+            if !app.task[index].tags.contains(&tag) {
+                app.task[index].tags.push(tag.clone());
+            }
+        }
+    }
+    clear_marks(app);
+}
+
+/// Returns the tag typed as `input`, without surrounding spaces or a leading '#'.
+pub fn tag_name(input: &str) -> &str {
+    input.trim().trim_start_matches('#')
+}
+
+/// Returns whether toggling `tag` would remove it, which it does when every target task already has it.
+pub fn tag_removes(app: &App, tag: &str) -> bool {
+    let marked: Vec<usize> = (0..app.task.len()).filter(|&index| app.is_marked(index)).collect();
+    let targets = if marked.is_empty() {
+        app.selected_task.filter(|&index| index < app.task.len()).into_iter().collect()
+    } else {
+        marked
+    };
+
+    !targets.is_empty() && targets.iter().all(|&index| app.task[index].tags.iter().any(|existing| existing == tag))
+}
+
+/// Abandons typing a tag.
+pub fn exit_tagging_mode(app: &mut App) {
+    app.input.clear();
+    app.input_mode = InputMode::Normal;
+}
+
+/// Marks or unmarks the selected task for a batch operation and moves the selection down.
+pub fn toggle_mark(app: &mut App) {
+    if matches!(app.input_mode, InputMode::Visual) {
+        commit_visual_range(app);
+        return;
+    }
+
+    if let Some(index) = app.selected_task.filter(|&index| index < app.task.len()) {
+        app.task[index].marked = !app.task[index].marked;
+        list_down(app);
+    }
+}
+
+/// Enters visual mode, where moving the selection marks the range between the starting task and the selection.
+pub fn visual_mode(app: &mut App) {
+    if app.selected_task.is_some() {
+        app.visual_anchor = app.selected_task;
+        app.input_mode = InputMode::Visual;
+    }
+}
+
+/// Leaves visual mode without marking the range.
+pub fn exit_visual_mode(app: &mut App) {
+    app.visual_anchor = None;
+    app.input_mode = InputMode::Normal;
+}
+
+/// Marks every task in the visual range and returns to normal mode. Does nothing outside visual mode.
+fn commit_visual_range(app: &mut App) {
+    if !matches!(app.input_mode, InputMode::Visual) {
+        return;
+    }
+
+    if let Some(range) = app.visual_range() {
+        for index in range {
+            if let Some(task) = app.task.get_mut(index) {
+                task.marked = true;
+            }
+        }
+    }
+    exit_visual_mode(app);
+}
+
+/// Unmarks every task.
+pub fn clear_marks(app: &mut App) {
+    for task in app.task.iter_mut() {
+        task.marked = false;
+    }
+}
+
+/// Starts editing the title of the selected task by loading it into the input buffer.
+pub fn edit_task(app: &mut App) {
+    if let Some(index) = app.selected_task.filter(|&index| index < app.task.len()) {
+        app.input = app.task[index].title.clone();
+        app.editing_task = Some(index);
+        app.input_mode = InputMode::Adding;
+        input_end(app);
+    }
+}
+
+/// Opens the trash view with the most recently deleted task selected.
+pub fn view_trash(app: &mut App) {
+    app.input_mode = InputMode::Trash;
+    app.selected_trash = app.trash.len().checked_sub(1);
+}
+
+/// Leaves the trash, archive or statistics view and returns to the task list.
+pub fn exit_view(app: &mut App) {
+    app.input_mode = InputMode::Normal;
+}
+
+/// Moves the selection up in the trash view, if possible.
+pub fn trash_up(app: &mut App) {
+    if let Some(index) = app.selected_trash {
+        if index > 0 {
+            app.selected_trash = Some(index - 1);
+        }
+    }
+}
+
+/// Moves the selection down in the trash view, if possible.
+pub fn trash_down(app: &mut App) {
+    if let Some(index) = app.selected_trash {
+        if index + 1 < app.trash.len() {
+            app.selected_trash = Some(index + 1);
+        }
+    }
+}
+
+/// Removes the selected task from the trash, returning it. Adjusts the trash selection if needed.
+fn take_selected_trash(app: &mut App) -> Option<TrashedTask> {
+    let index = app.selected_trash.filter(|&index| index < app.trash.len())?;
+    let trashed = app.trash.remove(index);
+    if app.trash.is_empty() {
+        app.selected_trash = None;
+    } else if index == app.trash.len() {
+        app.selected_trash = Some(index - 1);
+    }
+
+    Some(trashed)
+}
+
+/// Moves the selected task from the trash back to the end of the task list.
+pub fn restore_task(app: &mut App) {
+    if app.selected_trash.is_some() {
+        app.record_scope("restore task", Scope::Trash);
+    }
+    if let Some(trashed) = take_selected_trash(app) {
+        app.info(format!("Restored \"{}\"", trashed.task.title));
+        app.task.push(trashed.task);
+        app.selected_task = Some(app.task.len() - 1);
+    }
+}
+
+/// Permanently deletes the selected task from the trash.
+pub fn purge_task(app: &mut App) {
+    if app.selected_trash.is_some() {
+        app.record_scope("purge task", Scope::Trash);
+    }
+    take_selected_trash(app);
+}
+
+/// Permanently deletes every task in the trash.
+pub fn empty_trash(app: &mut App) {
+    if !app.trash.is_empty() {
+        app.record_scope("empty trash", Scope::Trash);
+        app.trash.clear();
+        app.selected_trash = None;
+    }
+}
+
+/// Moves every completed task from the list to the archive.
+pub fn archive_completed(app: &mut App) {
+    let count = app.task.iter().filter(|task| task.completed).count();
+    if count == 0 {
+        app.warn("No completed tasks to archive");
+        return;
+    }
+
+    app.record_scope("archive completed tasks", Scope::Archive);
+    archive_where(app, |task| task.completed);
+    app.info(format!("Archived {} completed task(s)", count));
+}
+
+/// Archives tasks that were completed more than `auto_archive_days` days ago, as one step that can be undone.
+/// Tasks completed before completion times were kept count as old enough. Zero disables automatic archiving.
+pub fn auto_archive(app: &mut App) {
+    let days = app.config.settings.auto_archive_days;
+    if days == 0 {
+        return;
+    }
+
+    let cutoff = unix_timestamp().saturating_sub(days * 24 * 60 * 60);
+    let expired = move |task: &Task| task.completed && task.completed_at.is_none_or(|completed_at| completed_at < cutoff);
+    if app.task.iter().any(expired) {
+        app.record_scope("auto-archive", Scope::Archive);
+        archive_where(app, expired);
+    }
+}
+
+/// Moves every task matching `predicate` to the archive, keeping the selection on a remaining task.
+fn archive_where(app: &mut App, predicate: impl Fn(&Task) -> bool) {
+    let (archived, remaining): (Vec<Task>, Vec<Task>) = app.task.drain(..).partition(|task| predicate(task));
+    app.archive.extend(archived.into_iter().map(ArchivedTask::new));
+    app.task = remaining;
+    app.selected_task = match app.selected_task {
+        _ if app.task.is_empty() => None,
+        Some(index) => Some(index.min(app.task.len() - 1)),
+        None => None,
+    };
+}
+
+/// Opens the read-only archive view, showing every archived task.
+pub fn view_archive(app: &mut App) {
+    app.input_mode = InputMode::Archive;
+    app.archive_query.clear();
+    app.selected_archive = if app.archive.is_empty() { None } else { Some(0) };
+}
+
+/// Switches from the task list to the statistics screen, or back.
+pub fn toggle_stats(app: &mut App) {
+    app.input_mode = match app.input_mode {
+        InputMode::Stats => InputMode::Normal,
+        _ => InputMode::Stats,
+    };
+}
+
+/// Moves the selection up in the archive view, if possible.
+pub fn archive_up(app: &mut App) {
+    if let Some(index) = app.selected_archive {
+        if index > 0 {
+            app.selected_archive = Some(index - 1);
+        }
+    }
+}
+
+/// Moves the selection down in the archive view, if possible.
+pub fn archive_down(app: &mut App) {
+    if let Some(index) = app.selected_archive {
+        if index + 1 < app.archive_results().len() {
+            app.selected_archive = Some(index + 1);
+        }
+    }
+}
+
+/// Starts typing a search query to filter the archive.
+pub fn start_archive_search(app: &mut App) {
+    app.input_mode = InputMode::ArchiveSearch;
+    input_end(app);
+}
+
+/// Stops typing the search query, keeping the archive filtered by it.
+pub fn finish_archive_search(app: &mut App) {
+    app.input_mode = InputMode::Archive;
+}
+
+/// Clears the search query and shows the whole archive again.
+pub fn cancel_archive_search(app: &mut App) {
+    app.archive_query.clear();
+    app.input_mode = InputMode::Archive;
+    update_archive_selection(app);
+}
+
+/// Selects the first search result, or nothing when no archived task matches the query.
+fn update_archive_selection(app: &mut App) {
+    app.selected_archive = if app.archive_results().is_empty() { None } else { Some(0) };
+}
+
+/// Restores the task list to the state before the last change, reporting what was undone.
+pub fn undo(app: &mut App) {
+    let scope = app.history.undo.last().map_or(Scope::Tasks, Snapshot::scope);
+    let current = app.snapshot("", scope);
+    match app.history.undo(current) {
+        Some(snapshot) => {
+            app.info(format!("Undone: {}", snapshot.description));
+            app.restore(snapshot);
+        }
+        None => app.warn("Nothing to undo"),
+    }
+}
+
+/// Reapplies the last undone change, reporting what was redone.
+pub fn redo(app: &mut App) {
+    let scope = app.history.redo.last().map_or(Scope::Tasks, Snapshot::scope);
+    let current = app.snapshot("", scope);
+    match app.history.redo(current) {
+        Some(snapshot) => {
+            app.info(format!("Redone: {}", snapshot.description));
+            app.restore(snapshot);
         }
+        None => app.warn("Nothing to redo"),
     }
 }
 
-/// Saves the current input as a new task by taking the input, creating a new task, and adding it to the list.
+/// Saves the current input as a new task by taking the input, creating a new task, and inserting it at the insert position.
+/// The new task becomes the selection, and the next task saved is placed right after it.
+/// When editing, the input replaces the title of the edited task instead and editing ends.
 pub fn save_task(app: &mut App) {
+    if let Some(index) = app.editing_task.filter(|&index| index < app.task.len()) {
+        let title: String = app.input.drain(..).collect();
+        if title != app.task[index].title {
+            app.record("edit task");
+            app.task[index].title = title;
+        }
+        exit_adding_mode(app);
+        return;
+    }
+
+    app.record("add task");
     let new_task = Task::new(app.input.drain(..).collect());
-    app.task.push(new_task);
+    app.cursor = 0;
+    let index = app.insert_index.min(app.task.len());
+    app.task.insert(index, new_task);
+    app.selected_task = Some(index);
+    app.insert_index = index + 1;
 }
 
-/// Exits the adding mode and sets the input mode back to `Normal`. Selects the first task if there is one.
+/// Exits the adding mode and sets the input mode back to `Normal`. Selects the first task if nothing is selected.
 pub fn exit_adding_mode(app: &mut App) {
     app.input_mode = InputMode::Normal;
-    app.selected_task = Some(0);
+    if app.editing_task.take().is_some() {
+        app.input.clear();
+    }
+    if app.task.is_empty() {
+        app.selected_task = None;
+    } else if app.selected_task.is_none() {
+        app.selected_task = Some(0);
+    }
 }
 
-/// Adds a character to the current input buffer, for when the user is typing to add a new task.
+/// Adds a character at the cursor in the input field of the current mode, for when the user is typing.
 pub fn input_add_char(app: &mut App, c: char) {
-    app.input.push(c);
+    let cursor = app.cursor;
+    if let Some(input) = app.active_input_mut() {
+        let cursor = cursor.min(input.chars().count());
+        input.insert(byte_index(input, cursor), c);
+        app.cursor = cursor + 1;
+        input_changed(app);
+    }
 }
 
-/// Removes the last character from the current input buffer, for when the user presses backspace while typing.
+/// Removes the character before the cursor from the input field, for when the user presses backspace while typing.
 pub fn input_del_char(app: &mut App) {
-    app.input.pop();
-}
\ No newline at end of file
+    let cursor = app.cursor;
+    if let Some(input) = app.active_input_mut() {
+        let cursor = cursor.min(input.chars().count());
+        if cursor > 0 {
+            input.remove(byte_index(input, cursor - 1));
+            app.cursor = cursor - 1;
+            input_changed(app);
+        }
+    }
+}
+
+/// Removes everything from the cursor to the end of the input field.
+pub fn input_kill_line(app: &mut App) {
+    let cursor = app.cursor;
+    if let Some(input) = app.active_input_mut() {
+        input.truncate(byte_index(input, cursor));
+        input_changed(app);
+    }
+}
+
+/// Moves the input cursor one character to the left.
+pub fn input_cursor_left(app: &mut App) {
+    app.cursor = app.cursor.saturating_sub(1);
+}
+
+/// Moves the input cursor one character to the right, stopping at the end of the text.
+pub fn input_cursor_right(app: &mut App) {
+    let len = app.active_input().map_or(0, |input| input.chars().count());
+    app.cursor = (app.cursor + 1).min(len);
+}
+
+/// Moves the input cursor to the start of the text.
+pub fn input_start(app: &mut App) {
+    app.cursor = 0;
+}
+
+/// Moves the input cursor to the end of the text.
+pub fn input_end(app: &mut App) {
+    app.cursor = app.active_input().map_or(0, |input| input.chars().count());
+}
+
+/// Returns the byte offset of the character at `cursor`, or the end of the text if it is past the end.
+fn byte_index(text: &str, cursor: usize) -> usize {
+    text.char_indices().nth(cursor).map_or(text.len(), |(index, _)| index)
+}
+
+/// Updates whatever depends on the text of a search field after it changes.
+fn input_changed(app: &mut App) {
+    match app.input_mode {
+        InputMode::ArchiveSearch => update_archive_selection(app),
+        InputMode::Search => select_search_match(app),
+        InputMode::Palette => app.selected_command = 0,
+        _ => {}
+    }
+}
+
+/// Leaves whatever the current mode is doing, like pressing the mode's own cancel key. This returns to the task
+/// list, except from an archive search, which returns to the archive, and from a confirmation prompt, a popup or the
+/// command palette, which return to the mode they were opened from.
+pub fn cancel(app: &mut App) {
+    app.count = None;
+    match app.input_mode {
+        InputMode::Normal => {}
+        InputMode::Adding => exit_adding_mode(app),
+        InputMode::Tagging => exit_tagging_mode(app),
+        InputMode::Search => cancel_task_search(app),
+        InputMode::ArchiveSearch => cancel_archive_search(app),
+        InputMode::Visual => exit_visual_mode(app),
+        InputMode::Confirm => decline(app),
+        InputMode::Trash | InputMode::Archive | InputMode::Stats => exit_view(app),
+        InputMode::Help | InputMode::Messages => toggle_popup(app, app.input_mode),
+        InputMode::Palette => close_palette(app),
+    }
+}
+
+/// Opens a popup, help or the message history, on top of the current mode, or closes it and returns to that
+/// mode.
+pub fn toggle_popup(app: &mut App, popup: InputMode) {
+    if app.input_mode == popup {
+        app.input_mode = app.popup_origin;
+    } else {
+        if !matches!(app.input_mode, InputMode::Help | InputMode::Messages) {
+            app.popup_origin = app.input_mode;
+        }
+        app.popup_scroll = 0;
+        app.input_mode = popup;
+    }
+}
+
+/// Scrolls the open popup by `lines`, negative to scroll up, keeping at least the last line in view.
+pub fn popup_scroll(app: &mut App, lines: isize) {
+    let total = match app.input_mode {
+        InputMode::Messages => app.messages.len(),
+        // Every section of help is a heading and its entries, with a blank line between sections.
+        _ => app.keymap.help_sections().iter().map(|(_, entries)| entries.len() + 2).sum::<usize>().saturating_sub(1),
+    };
+    let scroll = app.popup_scroll as isize;
+    app.popup_scroll = scroll.saturating_add(lines).clamp(0, total.saturating_sub(1) as isize) as usize;
+}
+
+/// Writes the tasks, trash, archive and undo history to disk now rather than on exit.
+pub fn save_to_disk(app: &mut App) {
+    match file_handler::save_all(app) {
+        Ok(()) => {
+            app.save_failed = false;
+            app.info("Saved");
+        }
+        Err(error) => app.error(format!("Could not save: {}", error)),
+    }
+}
+
+/// Opens the command palette on top of the current mode.
+pub fn open_palette(app: &mut App) {
+    app.palette_origin = app.input_mode;
+    app.command_input.clear();
+    app.selected_command = 0;
+    app.input_mode = InputMode::Palette;
+    input_start(app);
+}
+
+/// Closes the command palette and returns to the mode it was opened from.
+pub fn close_palette(app: &mut App) {
+    app.command_input.clear();
+    app.cursor = 0;
+    app.input_mode = app.palette_origin;
+}
+
+/// Selects the previous command in the palette, if possible.
+pub fn palette_up(app: &mut App) {
+    app.selected_command = app.selected_command.saturating_sub(1);
+}
+
+/// Selects the next command in the palette, if possible.
+pub fn palette_down(app: &mut App) {
+    let count = palette::matching_commands(app).len();
+    app.selected_command = (app.selected_command + 1).min(count.saturating_sub(1));
+}
+
+/// Closes the palette and runs the selected command with the argument typed after its name, if any. Commands
+/// without a binding in the mode the palette was opened from leave that mode first, e.g. visual mode.
+pub fn run_palette(app: &mut App) {
+    let command = palette::matching_commands(app).get(app.selected_command).copied();
+    let input = app.command_input.clone();
+    close_palette(app);
+
+    let command = match command {
+        Some(command) => command,
+        None => return app.error(format!("No command matches \"{}\"", input.trim())),
+    };
+
+    let mode = app.input_mode;
+    if !matches!(command, Command::Action(action) if app.keymap.bindings(mode).any(|(_, bound)| bound == action)) {
+        cancel(app);
+    }
+
+    match (command, palette::split_input(&input).1) {
+        (Command::Action(Action::TagTask), Some(tag)) => toggle_tag(app, tag),
+        (Command::Action(Action::Search), Some(text)) => search_for(app, text),
+        (Command::Action(Action::CycleTheme), Some(name)) => switch_theme(app, name),
+        (Command::Action(action), None) => action::dispatch(app, action),
+        (Command::Sort, Some(field)) => sort_tasks(app, field),
+        (Command::Sort, None) => {
+            app.error(format!("sort needs a field: {}", palette::SORT_FIELDS.join(", ")));
+        }
+        (Command::Note, text) => set_notes(app, text.unwrap_or_default()),
+        (Command::Due, date) => set_due(app, date),
+        (command, Some(_)) => app.error(format!("{} takes no argument", command.name())),
+    }
+}
+
+/// Switches to the theme after the active one, wrapping around to the first.
+pub fn cycle_theme(app: &mut App) {
+    let current = app.themes.iter().position(|theme| theme.name == app.theme.name);
+    let next = current.map_or(0, |index| (index + 1) % app.themes.len());
+    let name = app.themes[next].name.clone();
+    switch_theme(app, &name);
+}
+
+/// Switches to the theme called `name` for the rest of the session.
+fn switch_theme(app: &mut App, name: &str) {
+    if app.select_theme(name) {
+        app.info(format!("Theme: {}", name));
+    } else {
+        let names: Vec<&str> = app.themes.iter().map(|theme| theme.name.as_str()).collect();
+        app.error(format!("Unknown theme \"{}\", use one of: {}", name, names.join(", ")));
+    }
+}
+
+/// Selects the first task matching `text`, as if it had been typed into the search field.
+fn search_for(app: &mut App, text: &str) {
+    start_task_search(app);
+    app.task_query = text.to_string();
+    select_search_match(app);
+    finish_task_search(app);
+}
+
+/// Replaces the notes of the selected task with `text`, clearing them when it is empty.
+pub fn set_notes(app: &mut App, text: &str) {
+    let Some(index) = app.selected_task.filter(|&index| index < app.task.len()) else {
+        return app.warn("No task selected");
+    };
+
+    if app.task[index].notes == text {
+        return;
+    }
+
+    app.record(if text.is_empty() { "clear notes" } else { "edit notes" });
+    app.task[index].notes = text.to_string();
+    app.info(if text.is_empty() { "Notes cleared" } else { "Notes saved" });
+}
+
+/// Sets the due date of the selected task to `date`, written as `YYYY-MM-DD`, or clears it without a date.
+pub fn set_due(app: &mut App, date: Option<&str>) {
+    let Some(index) = app.selected_task.filter(|&index| index < app.task.len()) else {
+        return app.warn("No task selected");
+    };
+
+    let due = match date.map(parse_day) {
+        Some(Some(due)) => Some(due),
+        Some(None) => return app.error(format!("Invalid date \"{}\", use YYYY-MM-DD", date.unwrap_or_default())),
+        None => None,
+    };
+
+    if app.task[index].due == due {
+        return;
+    }
+
+    app.record(if due.is_some() { "set due date" } else { "clear due date" });
+    app.task[index].due = due;
+    match due {
+        Some(due) => app.info(format!("Due {}", format_day(due))),
+        None => app.info("Due date cleared"),
+    }
+}
+
+/// Sorts the task list by `field`, one of `palette::SORT_FIELDS`, keeping the selection on the same task.
+/// Equal tasks keep their order.
+pub fn sort_tasks(app: &mut App, field: &str) {
+    let compare: fn(&Task, &Task) -> Ordering = match field {
+        "title" => |a, b| a.title.to_lowercase().cmp(&b.title.to_lowercase()),
+        "priority" => |a, b| b.priority.cmp(&a.priority),
+        "created" => |a, b| a.created_at.cmp(&b.created_at),
+        // Tasks without a due date come after the ones with one.
+        "due" => |a, b| a.due.is_none().cmp(&b.due.is_none()).then(a.due.cmp(&b.due)),
+        "status" => |a, b| a.completed.cmp(&b.completed),
+        _ => {
+            app.error(format!(
+                "Unknown sort field \"{}\", use one of: {}",
+                field,
+                palette::SORT_FIELDS.join(", ")
+            ));
+            return;
+        }
+    };
+
+    app.record(&format!("sort tasks by {}", field));
+    let mut order: Vec<usize> = (0..app.task.len()).collect();
+    order.sort_by(|&a, &b| compare(&app.task[a], &app.task[b]));
+    app.selected_task = app.selected_task.and_then(|selected| order.iter().position(|&index| index == selected));
+    app.task = order.into_iter().map(|index| app.task[index].clone()).collect();
+}
+
+/// Formats `timestamp` as a UTC date and time, e.g. "2024-03-09 14:05 UTC".
+pub fn format_date(timestamp: u64) -> String {
+    let seconds = timestamp % 86400;
+    format!("{} {:02}:{:02} UTC", format_day(timestamp), seconds / 3600, seconds % 3600 / 60)
+}
+
+/// Formats the UTC day of `timestamp`, e.g. "2024-03-09".
+pub fn format_day(timestamp: u64) -> String {
+    // Converts days since the epoch to a civil date, following Howard Hinnant's `civil_from_days`.
+    let days = (timestamp / 86400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Parses a `YYYY-MM-DD` date into the timestamp of the start of that UTC day. Dates before 1970 and days that
+/// do not exist, such as February 30th, are rejected.
+pub fn parse_day(date: &str) -> Option<u64> {
+    let mut parts = date.trim().splitn(3, '-').map(|part| part.parse::<i64>().ok());
+    let (year, month, day) = (parts.next()??, parts.next()??, parts.next()??);
+    if !(1..=12).contains(&month) || day < 1 {
+        return None;
+    }
+
+    // Converts a civil date to days since the epoch, following Howard Hinnant's `days_from_civil`.
+    let march_year = if month <= 2 { year - 1 } else { year };
+    let era = march_year.div_euclid(400);
+    let year_of_era = march_year.rem_euclid(400);
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+
+    let timestamp = u64::try_from(days).ok()? * 86400;
+    // Days past the end of the month roll over into the next one, so they do not format back the same.
+    (format_day(timestamp) == format!("{:04}-{:02}-{:02}", year, month, day)).then_some(timestamp)
+}
+
+/// Formats the time elapsed since `timestamp` as a short human readable age, e.g. "3d ago".
+pub fn format_age(timestamp: u64) -> String {
+    let seconds = unix_timestamp().saturating_sub(timestamp);
+
+    match seconds {
+        0..=59 => "just now".to_string(),
+        60..=3599 => format!("{}m ago", seconds / 60),
+        3600..=86399 => format!("{}h ago", seconds / 3600),
+        _ => format!("{}d ago", seconds / 86400),
+    }
+}
+
+/// Formats a span of time in the two largest units that apply, e.g. "3d 4h" or "12m".
+pub fn format_duration(seconds: u64) -> String {
+    let (days, hours, minutes) = (seconds / 86400, seconds % 86400 / 3600, seconds % 3600 / 60);
+
+    match (days, hours, minutes) {
+        (0, 0, 0) => "under a minute".to_string(),
+        (0, 0, _) => format!("{}m", minutes),
+        (0, _, _) => format!("{}h {}m", hours, minutes),
+        _ => format!("{}d {}h", days, hours),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::Config, test_tasks};
+
+    #[test]
+    fn days_parse_and_format_back() {
+        assert_eq!(parse_day("1970-01-01"), Some(0));
+        assert_eq!(parse_day("2024-03-09"), Some(1_709_942_400));
+        assert_eq!(format_day(1_709_942_400 + 3600), "2024-03-09");
+        for date in ["2000-02-29", "2024-12-31", "2026-10-19", "2100-03-01"] {
+            assert_eq!(parse_day(date).map(format_day).as_deref(), Some(date));
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_days() {
+        for date in ["", "2024", "2024-13-01", "2024-02-30", "2023-02-29", "1969-12-31", "2024-1-x", "today"] {
+            assert_eq!(parse_day(date), None, "{}", date);
+        }
+    }
+
+    #[test]
+    fn sorts_by_due_date_with_undated_tasks_last() {
+        let mut app = App::new(&test_tasks(&["later", "none", "sooner"]), Config::default());
+        app.task[0].due = parse_day("2026-11-01");
+        app.task[2].due = parse_day("2026-10-20");
+
+        sort_tasks(&mut app, "due");
+        let titles: Vec<&str> = app.task.iter().map(|task| task.title.as_str()).collect();
+        assert_eq!(titles, ["sooner", "later", "none"]);
+        assert_eq!(app.selected_task, Some(1));
+    }
+    #[test]
+    fn auto_archive_can_be_undone() {
+        let mut config = Config::default();
+        config.settings.auto_archive_days = 7;
+        let mut app = App::new(&test_tasks(&["old", "open", "untimed", "recent"]), config);
+        app.task[0].completed = true;
+        app.task[0].completed_at = Some(unix_timestamp() - 30 * 86400);
+        app.task[2].completed = true;
+        app.task[3].set_completed(true);
+
+        auto_archive(&mut app);
+        let titles: Vec<&str> = app.task.iter().map(|task| task.title.as_str()).collect();
+        assert_eq!(titles, ["open", "recent"]);
+        assert_eq!(app.archive.len(), 2);
+
+        undo(&mut app);
+        assert_eq!(app.task.len(), 4);
+        assert!(app.archive.is_empty());
+    }
+    #[test]
+    fn palette_commands_unbound_in_visual_mode_leave_it_first() {
+        let mut app = App::new(&test_tasks(&["a", "b", "c"]), Config::default());
+        visual_mode(&mut app);
+        list_down(&mut app);
+
+        open_palette(&mut app);
+        app.command_input = "view_trash".to_string();
+        run_palette(&mut app);
+        assert_eq!(app.input_mode, InputMode::Trash);
+        assert_eq!(app.visual_anchor, None);
+        assert!(app.task.iter().all(|task| !task.marked));
+    }
+
+    #[test]
+    fn unchanged_due_dates_are_not_recorded() {
+        let mut app = App::new(&test_tasks(&["a"]), Config::default());
+        set_due(&mut app, Some("2026-10-20"));
+        set_due(&mut app, Some("2026-10-20"));
+        assert_eq!(app.history.undo.len(), 1);
+    }
+}
@@ -1,175 +1,1031 @@
 ///----------widget.rs----------///
 
 use tui::{
-    layout::{Constraint, Direction, Layout, Rect},
-    style::Modifier,
-    text::{Span, Spans, Text},
-    widgets::{Block, BorderType, Borders, List, ListItem, Paragraph},
+    buffer::Buffer,
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    symbols,
+    text::{Span, Spans},
+    widgets::{BarChart, Block, BorderType, Borders, Gauge, LineGauge, List, ListItem, Paragraph, Sparkline, Widget},
 };
+use std::mem;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
-use crate::{config::keycode_to_string, App, InputMode, Task};
+use crate::{
+    config::{BorderStyle, DetailPosition, InputPosition},
+    keys::{KeySequence, Keys},
+    palette::{self, Command},
+    stats::Stats,
+    utils::{format_age, format_date, format_day, format_duration},
+    unix_timestamp, App, InputMode, Priority, Task,
+};
+
+/// Terminals narrower or shorter than this are laid out compactly, without margins and with a shorter hint.
+const COMPACT_SIZE: (u16, u16) = (60, 20);
+/// Terminals at least this wide show the details of the selected task next to the list.
+const WIDE_WIDTH: u16 = 120;
+/// Bars of the charts in ASCII mode, from full to empty.
+const ASCII_BARS: symbols::bar::Set = symbols::bar::Set {
+    full: "#",
+    seven_eighths: "#",
+    three_quarters: "=",
+    five_eighths: "=",
+    half: "-",
+    three_eighths: "-",
+    one_quarter: ".",
+    one_eighth: ".",
+    empty: " ",
+};
+
+/// The sections of the screen, laid out for the size of the terminal.
+pub struct MainLayout {
+    pub input: Option<Rect>,    // Input field, unless it is a popup that is not open.
+    pub list: Rect,             // Task list, trash, archive or statistics.
+    pub detail: Option<Rect>,   // Details of the selected task, when they are shown.
+    pub progress: Option<Rect>, // Completion gauge on the bottom border of the task list, when it is switched on.
+    pub hint: Option<Rect>,     // Navigation hint, unless it is switched off.
+    pub status: Rect,           // Status line with the latest message.
+    pub compact: bool,          // Whether the terminal is small enough for the compact layout.
+}
+
+/// Divides the terminal window into main sections to organize the display of various UI components, following the
+/// layout settings. Small terminals drop the margins, and the list section is split to make room for the task
+/// details on wide terminals or when they are toggled on. An input popup is centered over the list while text is
+/// typed into it.
+pub fn main_chunks(app: &App, area: Rect) -> MainLayout {
+    let settings = &app.config.layout;
+    let compact = area.width < COMPACT_SIZE.0 || area.height < COMPACT_SIZE.1;
+
+    // The input field takes a row of the layout unless it is a popup.
+    let input_row = match settings.input_position {
+        InputPosition::Top => Some(0),
+        InputPosition::Bottom => Some(1),
+        InputPosition::Popup => None,
+    };
+    let mut constraints = vec![
+        Constraint::Min(1),    // Minimum size for the task list.
+        Constraint::Length(1), // Fixed size for the status line.
+    ];
+    if settings.show_hint {
+        constraints.insert(1, Constraint::Length(1)); // Fixed size for the navigation hint.
+    }
+    if let Some(row) = input_row {
+        constraints.insert(row, Constraint::Length(3)); // Fixed size for the input field.
+    }
 
-/// Divides the terminal window into main sections to organize the display of various UI components.
-pub fn main_chunks(area: Rect) -> Vec<Rect> {
-    let chunks = Layout::default()
+    let mut chunks = Layout::default()
         .direction(Direction::Vertical)
-        .margin(2)
-        .constraints(
-            [
-                Constraint::Length(3), // Fixed size for the input field.
-                Constraint::Min(1),    // Minimum size for the task list.
-                Constraint::Length(1), // Fixed size for the navigation hint.
-            ]
-            .as_ref(),
-        )
+        .margin(if compact { 0 } else { settings.margin })
+        .constraints(constraints)
         .split(area);
 
-    chunks
+    let input = match input_row {
+        Some(row) => Some(chunks.remove(row)),
+        None if app.input_mode.accepts_text() && app.input_mode != InputMode::Palette => {
+            Some(fixed_rect((area.width * 3 / 5).max(40), 3, chunks[0]))
+        }
+        None => None,
+    };
+    let hint = settings.show_hint.then(|| chunks.remove(1));
+
+    // The details are shown on wide terminals unless they have been toggled off, and toggled on elsewhere. The
+    // statistics screen takes the whole section.
+    let shows_detail = (area.width >= WIDE_WIDTH) != app.detail_toggled;
+    let (list, detail) = if shows_detail && app.view_mode() != InputMode::Stats {
+        let direction = match settings.detail_position {
+            DetailPosition::Side => Direction::Horizontal,
+            DetailPosition::Bottom => Direction::Vertical,
+        };
+        let parts = Layout::default()
+            .direction(direction)
+            .constraints([Constraint::Percentage(60), Constraint::Percentage(40)].as_ref())
+            .split(chunks[0]);
+        (parts[0], Some(parts[1]))
+    } else {
+        (chunks[0], None)
+    };
+
+    // The gauge replaces the middle of the bottom border of the task list, leaving the corners alone.
+    let shows_tasks = !matches!(
+        app.view_mode(),
+        InputMode::Trash | InputMode::Archive | InputMode::ArchiveSearch | InputMode::Stats
+    );
+    let progress = (settings.progress_gauge && shows_tasks && list.height >= 3 && list.width >= 3)
+        .then(|| Rect::new(list.x + 1, list.y + list.height - 1, list.width - 2, 1));
+
+    MainLayout {
+        input,
+        list,
+        detail,
+        progress,
+        hint,
+        status: chunks[1],
+        compact,
+    }
 }
 
-/// Creates a list widget displaying all tasks, utilizing `ListItem` for individual tasks.
-pub fn task_list(app: &App) -> List {
+/// Creates a list widget displaying the tasks from `app.task_offset` on, utilizing `ListItem` for individual tasks.
+/// Titles too long for a list `width` columns wide, borders included, wrap onto further rows.
+pub fn task_list(app: &App, width: u16) -> List {
     // Converts each task in the application to a `ListItem` for rendering.
     let task: Vec<ListItem> = app
         .task
         .iter()
         .enumerate()
-        .map(|q| indexed_task_item(app, q))
+        .skip(app.task_offset)
+        .map(|q| indexed_task_item(app, q, width.saturating_sub(2)))
         .collect();
 
     // Constructs the list with a styled border and title.
     List::new(task).style(app.default_style()).block(
         Block::default()
-            .title("Tasks")
+            .title(task_list_title(app))
             .borders(Borders::ALL)
-            .border_type(BorderType::Rounded)
+            .border_type(border_type(app))
             .style(app.default_style()),
     )
 }
 
-/// Determines the visual representation of a task in the list based on its selection and completion status.
-fn indexed_task_item<'a>(app: &'a App, (index, task): (usize, &Task)) -> ListItem<'a> {
-    if let Some(selected_index) = app.selected_task {
-        task_item(
-            task.title.clone(),
-            task.completed,
-            selected_index == index,
-            app,
-        )
-    } else {
-        task_item(task.title.clone(), task.completed, false, app)
+/// Returns the title of the task list, with the completed task counts in the configured format. The `{done}`,
+/// `{open}`, `{total}` and `{percent}` placeholders are replaced by the counts of the tasks matching the active
+/// search, if any.
+fn task_list_title(app: &App) -> String {
+    let format = &app.config.layout.progress_format;
+    if format.is_empty() {
+        return "Tasks".to_string();
+    }
+
+    let (done, total) = app.progress();
+    let counts = format
+        .replace("{done}", &done.to_string())
+        .replace("{open}", &(total - done).to_string())
+        .replace("{total}", &total.to_string())
+        .replace("{percent}", &format!("{:.0}", completion_ratio(done, total) * 100.0));
+    match app.counted_query() {
+        None => format!("Tasks ({})", counts),
+        Some(query) => format!("Tasks ({}) matching \"{}\"", counts, query),
+    }
+}
+
+/// Provides a one row gauge of the share of counted tasks that are completed, for the bottom border of the list.
+pub fn progress_gauge(app: &App) -> LineGauge {
+    let (done, total) = app.progress();
+    let ratio = completion_ratio(done, total);
+    let filled = app.theme.check_sign.fg.or(app.default_style().fg);
+    let empty = app.theme.muted.fg.or(app.default_style().fg);
+
+    LineGauge::default()
+        .style(app.default_style())
+        .gauge_style(Style { fg: filled, bg: empty, ..Style::default() })
+        .ratio(ratio)
+        .line_set(if app.config.glyphs.border == BorderStyle::Ascii { ASCII_LINES } else { symbols::line::NORMAL })
+        .label(Span::styled(format!("{:.0}%", ratio * 100.0), app.muted_style(false)))
+}
+
+/// Returns the share of `total` tasks that `done` stands for, from 0 to 1.
+fn completion_ratio(done: usize, total: usize) -> f64 {
+    match total {
+        0 => 0.0,
+        total => done as f64 / total as f64,
     }
 }
 
+/// Returns the number of rows each task takes up in a task list `width` columns wide, borders included.
+pub fn task_heights(app: &App, width: u16) -> Vec<usize> {
+    app.task
+        .iter()
+        .map(|task| task_rows(task, false, false, app, width.saturating_sub(2)).len())
+        .collect()
+}
+
+/// Creates a list widget displaying the deleted tasks in the trash along with how long ago they were deleted.
+pub fn trash_list(app: &App) -> List {
+    let trash: Vec<ListItem> = app
+        .trash
+        .iter()
+        .enumerate()
+        .map(|(index, trashed)| {
+            let selected = app.selected_trash == Some(index);
+            let style = if selected {
+                app.selection_style()
+            } else {
+                app.default_style()
+            };
+
+            ListItem::new(Spans::from(vec![
+                Span::styled(format!("{:>9}  ", format_age(trashed.deleted_at)), app.muted_style(selected)),
+                Span::styled(trashed.task.title.clone(), style),
+            ]))
+            .style(style)
+        })
+        .collect();
+
+    List::new(trash).style(app.default_style()).block(
+        Block::default()
+            .title(format!("Trash ({})", app.trash.len()))
+            .borders(Borders::ALL)
+            .border_type(border_type(app))
+            .style(app.default_style()),
+    )
+}
+
+/// Creates a read-only list widget displaying the archived tasks that match the current search query.
+pub fn archive_list(app: &App) -> List {
+    let results = app.archive_results();
+
+    let archive: Vec<ListItem> = results
+        .iter()
+        .enumerate()
+        .map(|(index, archived)| {
+            let selected = app.selected_archive == Some(index);
+            let style = if selected {
+                app.selection_style()
+            } else {
+                app.default_style()
+            };
+
+            ListItem::new(Spans::from(vec![
+                Span::styled(format!("{:>9}  ", format_age(archived.archived_at)), app.muted_style(selected)),
+                Span::styled(archived.task.title.clone(), style),
+            ]))
+            .style(style)
+        })
+        .collect();
+
+    let title = if app.archive_query.is_empty() {
+        format!("Archive ({})", app.archive.len())
+    } else {
+        format!("Archive ({} of {} match \"{}\")", results.len(), app.archive.len(), app.archive_query)
+    };
+
+    List::new(archive).style(app.default_style()).block(
+        Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_type(border_type(app))
+            .style(app.default_style()),
+    )
+}
+
+/// Determines the visual representation of a task in the list based on its selection, marking and completion status.
+/// The selection is not highlighted while a new task is being typed.
+fn indexed_task_item<'a>(app: &'a App, (index, task): (usize, &Task), width: u16) -> ListItem<'a> {
+    let selected = app.selected_task == Some(index) && !matches!(app.input_mode, InputMode::Adding);
+    task_item(task, app.is_marked(index), selected, app, width)
+}
+
+/// Returns the width of the marker column in front of each task, which fits the marker and a space.
+pub fn marker_width(app: &App) -> u16 {
+    app.config.glyphs.marker.width() as u16 + 1
+}
+
+/// Returns the width of the check column after the marker, which fits either check glyph or tree glyph and two
+/// spaces.
+pub fn check_width(app: &App) -> u16 {
+    let glyphs = &app.config.glyphs;
+    [&glyphs.check, &glyphs.uncheck, &glyphs.tree_middle, &glyphs.tree_last]
+        .iter()
+        .map(|glyph| glyph.width())
+        .max()
+        .unwrap_or(0) as u16
+        + 2
+}
+
+/// Pads `text` with spaces to `width` columns on the terminal.
+fn pad(text: &str, width: u16) -> String {
+    format!("{}{}", text, " ".repeat((width as usize).saturating_sub(text.width())))
+}
+
 /// Configures the appearance of a single task item in the list, adjusting style for completion and selection.
-fn task_item(title: String, completed: bool, selected: bool, app: &App) -> ListItem {
+/// The style covers every row of a wrapped title, so the whole task is highlighted when it is selected.
+fn task_item<'a>(task: &Task, marked: bool, selected: bool, app: &'a App, width: u16) -> ListItem<'a> {
     let style = if selected {
         app.selection_style()
     } else {
         app.default_style()
     };
 
-    let task = if completed {
-        ListItem::new(Spans::from(vec![
-            Span::styled("✔  ", app.check_sign_style(selected)),
-            Span::styled(title, app.checked_task_style(selected)),
-        ]))
+    ListItem::new(task_rows(task, marked, selected, app, width)).style(style)
+}
+
+/// Lays out a task in rows `width` columns wide. Marked tasks get a marker in the first column, followed by the
+/// check column, priority, title and tags, which wrap at spaces onto rows indented under the title.
+fn task_rows<'a>(task: &Task, marked: bool, selected: bool, app: &'a App, width: u16) -> Vec<Spans<'a>> {
+    let style = if selected {
+        app.selection_style()
     } else {
-        ListItem::new(Spans::from(vec![
-            Span::styled("   ", style),
-            Span::styled(title, style),
-        ]))
+        app.default_style()
     };
 
-    task.style(style)
+    let glyphs = &app.config.glyphs;
+    let check = if task.completed { &glyphs.check } else { &glyphs.uncheck };
+    let prefix = vec![
+        Span::styled(pad(if marked { &glyphs.marker } else { "" }, marker_width(app)), app.check_sign_style(selected)),
+        Span::styled(pad(check, check_width(app)), app.check_sign_style(selected)),
+    ];
+
+    let mut segments = Vec::new();
+    if let Some(priority) = task.priority {
+        let glyph = match priority {
+            Priority::Low => &glyphs.priority_low,
+            Priority::Medium => &glyphs.priority_medium,
+            Priority::High => &glyphs.priority_high,
+        };
+        segments.push((format!("{} ", glyph), app.priority_style(selected)));
+    }
+    let title_style = if task.completed { app.checked_task_style(selected) } else { style };
+    segments.push((task.title.clone(), title_style));
+    for tag in &task.tags {
+        segments.push((format!(" #{}", tag), app.tag_style(selected)));
+    }
+
+    let indent = marker_width(app) + check_width(app);
+    let mut rows = wrap(&segments, width.saturating_sub(indent) as usize).into_iter();
+    let mut first = prefix;
+    first.extend(rows.next().unwrap_or_default());
+
+    // Wrapped rows hang from the check column like the branches of a tree.
+    let mut lines = vec![Spans::from(first)];
+    let continued = rows.len();
+    for (index, row) in rows.enumerate() {
+        let branch = if index + 1 == continued { &glyphs.tree_last } else { &glyphs.tree_middle };
+        let mut line = vec![
+            Span::styled(pad("", marker_width(app)), style),
+            Span::styled(pad(branch, check_width(app)), app.muted_style(selected)),
+        ];
+        line.extend(row);
+        lines.push(Spans::from(line));
+    }
+    lines
+}
+
+/// Wraps styled text into rows at most `width` columns wide, breaking between words where possible and within
+/// words longer than a row. Spaces at the start of a wrapped row are dropped.
+fn wrap<'a>(segments: &[(String, Style)], width: usize) -> Vec<Vec<Span<'a>>> {
+    let width = width.max(1);
+    let mut rows: Vec<Vec<Span>> = vec![Vec::new()];
+    let mut used = 0;
+
+    for (text, style) in segments {
+        for word in text.split_inclusive(' ') {
+            if used > 0 && used + word.trim_end().width() > width {
+                rows.push(Vec::new());
+                used = 0;
+            }
+            let word = if used == 0 && rows.len() > 1 { word.trim_start() } else { word };
+
+            let mut piece = String::new();
+            for c in word.chars() {
+                let c_width = c.width().unwrap_or(0);
+                if used > 0 && used + c_width > width && c != ' ' {
+                    if let Some(row) = rows.last_mut() {
+                        row.push(Span::styled(mem::take(&mut piece), *style));
+                    }
+                    rows.push(Vec::new());
+                    used = 0;
+                }
+                piece.push(c);
+                used += c_width;
+            }
+            if let Some(row) = rows.last_mut().filter(|_| !piece.is_empty()) {
+                row.push(Span::styled(piece, *style));
+            }
+        }
+    }
+
+    rows
+}
+
+/// Creates the pane with every detail of the selected task, scrolled by `app.detail_scroll`. The full title
+/// and the other fields wrap to the `width` of the pane, borders included.
+pub fn task_detail(app: &App, width: u16) -> Paragraph {
+    let block = Block::default()
+        .title("Details")
+        .borders(Borders::ALL)
+        .border_type(border_type(app))
+        .style(app.default_style());
+
+    Paragraph::new(task_detail_rows(app, width))
+        .style(app.default_style())
+        .scroll((app.detail_scroll.min(u16::MAX as usize) as u16, 0))
+        .block(block)
+}
+
+/// Lays out the details of the selected task in rows that fit a pane `width` columns wide, borders included.
+pub fn task_detail_rows(app: &App, width: u16) -> Vec<Spans> {
+    let width = width.saturating_sub(2) as usize;
+    let Some((index, task)) = app.selected_task.and_then(|index| app.task.get(index).map(|task| (index, task)))
+    else {
+        return vec![Spans::from(Span::styled("No task selected", app.muted_style(false)))];
+    };
+
+    let date = |timestamp: Option<u64>| match timestamp {
+        Some(timestamp) => format!("{} ({})", format_date(timestamp), format_age(timestamp)),
+        None => "unknown".to_string(),
+    };
+    let due = match task.due {
+        Some(due) if !task.completed && due + 86400 <= unix_timestamp() => format!("{} (overdue)", format_day(due)),
+        Some(due) => format_day(due),
+        None => "none".to_string(),
+    };
+    let tags: Vec<String> = task.tags.iter().map(|tag| format!("#{}", tag)).collect();
+    let fields = [
+        ("Position", format!("{} of {}", index + 1, app.task.len())),
+        ("Status", if task.completed { "done" } else { "open" }.to_string()),
+        ("Priority", task.priority.map_or("none", Priority::label).to_string()),
+        ("Due", due),
+        ("Tags", if tags.is_empty() { "none".to_string() } else { tags.join(" ") }),
+        ("Created", date(task.created_at)),
+        ("Completed", if task.completed { date(task.completed_at) } else { "not yet".to_string() }),
+        ("Marked", if app.is_marked(index) { "yes" } else { "no" }.to_string()),
+        ("Notes", if task.notes.is_empty() { "none".to_string() } else { task.notes.clone() }),
+    ];
+
+    // Field values wrap onto rows indented under the first value, and so do the lines of multi-line notes.
+    const LABEL_WIDTH: usize = 11;
+    let title = [(task.title.clone(), app.default_style().add_modifier(Modifier::BOLD))];
+    let mut rows: Vec<Spans> = wrap(&title, width).into_iter().map(Spans::from).collect();
+    rows.push(Spans::default());
+    for (label, value) in fields {
+        let value_rows = value
+            .lines()
+            .flat_map(|line| wrap(&[(line.to_string(), app.default_style())], width.saturating_sub(LABEL_WIDTH)));
+        for (line, row) in value_rows.enumerate() {
+            let label = if line == 0 { label } else { "" };
+            let label = format!("{:<width$}", label, width = LABEL_WIDTH);
+            let mut spans = vec![Span::styled(label, app.muted_style(false))];
+            spans.extend(row);
+            rows.push(Spans::from(spans));
+        }
+    }
+    rows
 }
 
 /// Provides an input field for adding new tasks with visual feedback on the current input mode.
+/// While browsing the archive the field shows the archive search query instead.
 pub fn task_input(app: &App) -> Paragraph {
     let style = match app.input_mode {
-        InputMode::Normal => app.default_style(),
-        InputMode::Adding => app.default_style().fg(app.config.colors.selection_bg),
-        InputMode::ConfirmQuit => app.default_style(),
+        InputMode::Normal | InputMode::Help | InputMode::Messages | InputMode::Palette => app.default_style(),
+        InputMode::Adding | InputMode::ArchiveSearch | InputMode::Tagging | InputMode::Search => {
+            app.active_input_style()
+        }
+        InputMode::Confirm | InputMode::Trash | InputMode::Archive | InputMode::Visual | InputMode::Stats => {
+            app.default_style()
+        }
+    };
+
+    let (title, text) = match app.view_mode() {
+        InputMode::Tagging => ("Add or remove tag", app.input.as_str()),
+        InputMode::Search => ("Search tasks", app.task_query.as_str()),
+        InputMode::Archive | InputMode::ArchiveSearch => ("Search archive", app.archive_query.as_str()),
+        _ if app.editing_task.is_some() => ("Edit task", app.input.as_str()),
+        _ => ("Add task", app.input.as_str()),
     };
 
-    let input = Paragraph::new(app.input.as_ref()).style(style).block(
+    let input = Paragraph::new(text).style(style).block(
         Block::default()
             .borders(Borders::ALL)
-            .title("Add task")
-            .border_type(BorderType::Rounded)
+            .title(title)
+            .border_type(border_type(app))
             .style(style),
     );
 
     input
 }
 
-/// Displays a help section at the bottom of the UI with keyboard shortcuts for various actions, styled differently based on the input mode.
-pub fn navigation_hint(app: &App) -> Paragraph {
-    let keybindings = &app.config.keybindings;
+/// Displays a hint at the bottom of the UI with the most relevant keyboard shortcuts of the current mode, cut
+/// short to fit `width`, and to the three most relevant in the `compact` layout. A partly typed key sequence takes
+/// the place of the hint.
+pub fn navigation_hint(app: &App, width: u16, compact: bool) -> Paragraph {
+    let kb = &app.config.keybindings;
 
-    let (msg, style) = match app.input_mode {
-        InputMode::Normal => (
-            // Keyboard shortcuts help text for normal mode, styled bold for keys and blinking for the text.
-            vec![
-                Span::styled(
-                    keycode_to_string(keybindings.exit_app),
-                    app.default_style().add_modifier(Modifier::BOLD),
-                ),
-                Span::styled(" exit | ", app.default_style()),
-                Span::styled(
-                    keycode_to_string(keybindings.new_task),
-                    app.default_style().add_modifier(Modifier::BOLD),
-                ),
-                Span::styled(" add task | ", app.default_style()),
-                Span::styled(
-                    keycode_to_string(keybindings.check_and_uncheck_task),
-                    app.default_style().add_modifier(Modifier::BOLD),
-                ),
-                Span::styled(" check/uncheck task | ", app.default_style()),
-                Span::styled(
-                    format!(
-                        "{}/{}",
-                        keycode_to_string(keybindings.list_up),
-                        keycode_to_string(keybindings.list_down)
-                    ),
-                    app.default_style().add_modifier(Modifier::BOLD),
-                ),
-                Span::styled(" navigate list | ", app.default_style()),
+    // While a count or a multi-key binding is being typed, show the keys so far instead of the hint.
+    if app.count.is_some() || !app.pending_keys.is_empty() {
+        let count = app.count.map(|count| count.to_string()).unwrap_or_default();
+        let pending = format!("{}{}", count, KeySequence(app.pending_keys.clone()));
+        return Paragraph::new(Spans::from(vec![
+            Span::styled(pending, app.key_style()),
+            Span::styled(format!(" {} waiting for the next key", app.config.glyphs.ellipsis), app.default_style()),
+        ]))
+        .style(app.default_style());
+    }
+
+    // The most relevant keys of each mode, most important first.
+    let hints: Vec<(&Keys, &str)> = match app.input_mode {
+        InputMode::Normal => vec![
+            (&kb.new_task, "add"),
+            (&kb.check_and_uncheck_task, "check"),
+            (&kb.delete_task, "delete"),
+            (&kb.search, "search"),
+            (&kb.toggle_details, "details"),
+            (&kb.command_palette, "commands"),
+            (&kb.exit_app, "exit"),
+        ],
+        InputMode::Adding => vec![(&kb.save_task, "save"), (&kb.exit_adding_mode, "cancel")],
+        InputMode::Tagging => vec![(&kb.save_task, "add/remove tag"), (&kb.exit_adding_mode, "cancel")],
+        InputMode::Search => vec![(&kb.save_task, "done"), (&kb.exit_adding_mode, "cancel")],
+        InputMode::ArchiveSearch => vec![(&kb.save_task, "done"), (&kb.exit_adding_mode, "clear search")],
+        InputMode::Visual => vec![(&kb.toggle_mark, "mark range"), (&kb.exit_view, "cancel")],
+        InputMode::Trash => vec![
+            (&kb.restore_task, "restore"),
+            (&kb.purge_task, "purge"),
+            (&kb.empty_trash, "empty trash"),
+            (&kb.exit_view, "back"),
+        ],
+        InputMode::Archive => vec![(&kb.search, "search"), (&kb.exit_view, "back")],
+        InputMode::Stats => vec![(&kb.view_stats, "task list"), (&kb.cycle_theme, "theme")],
+        InputMode::Help => vec![(&kb.list_down, "scroll"), (&kb.help, "close")],
+        InputMode::Messages => vec![(&kb.list_down, "scroll"), (&kb.message_history, "close")],
+        InputMode::Palette => vec![
+            (&kb.save_task, "run"),
+            (&kb.next_command, "next"),
+            (&kb.exit_adding_mode, "close"),
+        ],
+        InputMode::Confirm => vec![(&kb.confirm_yes, "yes"), (&kb.confirm_no, "no")],
+    };
+
+    // The help key is always listed last, where it is shown even when other hints have to make room for it.
+    let help = match app.input_mode {
+        InputMode::Normal | InputMode::Visual | InputMode::Trash | InputMode::Archive | InputMode::Stats => {
+            first_key(&kb.help)
+        }
+        _ => None,
+    }
+    .map(|key| (key, "help"));
+
+    let hints: Vec<(String, &str)> = hints
+        .into_iter()
+        .filter_map(|(keys, label)| first_key(keys).map(|key| (key, label)))
+        .take(if compact { 3 } else { usize::MAX })
+        .collect();
+
+    let separator = if compact { "  " } else { " | " };
+    Paragraph::new(Spans::from(hint_spans(app, &hints, help, separator, width as usize))).style(app.default_style())
+}
+
+/// Returns the first key sequence of a binding, or nothing if the action is unbound.
+fn first_key(keys: &Keys) -> Option<String> {
+    keys.0.first().map(|sequence| sequence.to_string())
+}
+
+/// Displays the latest message in the status line, colored by its level, until it expires.
+pub fn status_line(app: &App) -> Paragraph {
+    let text = match app.current_message() {
+        Some(message) => Spans::from(Span::styled(message.text.as_str(), app.message_style(message.level))),
+        None => Spans::default(),
+    };
+
+    Paragraph::new(text).style(app.default_style())
+}
+
+/// Creates the popup listing the messages reported this session, newest first, scrolled by `app.popup_scroll`.
+pub fn messages_popup(app: &App) -> Paragraph {
+    let lines: Vec<Spans> = app
+        .messages
+        .iter()
+        .rev()
+        .map(|message| {
+            Spans::from(vec![
                 Span::styled(
-                    keycode_to_string(keybindings.delete_task),
-                    app.default_style().add_modifier(Modifier::BOLD),
+                    format!("{:>9}  ", format_age(message.created_at)),
+                    app.muted_style(false),
                 ),
-                Span::styled(" delete task", app.default_style()),
-            ],
-            app.default_style(),
-        ),
-        InputMode::Adding => (
-            // Keyboard shortcuts for adding mode
-            vec![
+                Span::styled(message.text.as_str(), app.message_style(message.level)),
+            ])
+        })
+        .collect();
+
+    let title = format!("Messages ({})", app.messages.len());
+    Paragraph::new(lines)
+        .style(app.default_style())
+        .scroll((app.popup_scroll.min(u16::MAX as usize) as u16, 0))
+        .block(
+            Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .border_type(border_type(app))
+                .style(app.default_style()),
+        )
+}
+
+/// Lays out `key label` pairs divided by `separator`, dropping the pairs that would not fit in `width` characters but
+/// always keeping room for `last`.
+fn hint_spans<'a>(
+    app: &App,
+    hints: &[(String, &'a str)],
+    last: Option<(String, &'a str)>,
+    separator: &'a str,
+    width: usize,
+) -> Vec<Span<'a>> {
+    let entry_width = |(key, label): &(String, &str)| key.chars().count() + 1 + label.chars().count();
+    let reserved = last.as_ref().map_or(0, |entry| entry_width(entry) + separator.len());
+
+    let mut spans = Vec::new();
+    let mut used = 0;
+    let push = |spans: &mut Vec<Span<'a>>, (key, label): (String, &'a str)| {
+        if !spans.is_empty() {
+            spans.push(Span::styled(separator, app.default_style()));
+        }
+        spans.push(Span::styled(key, app.key_style()));
+        spans.push(Span::styled(format!(" {}", label), app.default_style()));
+    };
+
+    for entry in hints {
+        let needed = entry_width(entry) + if used == 0 { 0 } else { separator.len() };
+        if used + needed + reserved > width {
+            break;
+        }
+        used += needed;
+        push(&mut spans, entry.clone());
+    }
+
+    if let Some(entry) = last {
+        if used + entry_width(&entry) + if used == 0 { 0 } else { separator.len() } <= width {
+            push(&mut spans, entry);
+        }
+    }
+
+    spans
+}
+
+/// Creates the help overlay listing the key bindings of every mode, scrolled by `app.popup_scroll`.
+pub fn help_popup(app: &App) -> Paragraph {
+    let sections = app.keymap.help_sections();
+    let key_width = sections
+        .iter()
+        .flat_map(|(_, entries)| entries.iter().map(|(keys, _)| keys.chars().count()))
+        .max()
+        .unwrap_or(0);
+
+    let mut lines = Vec::new();
+    for (heading, entries) in sections {
+        lines.push(Spans::from(Span::styled(
+            heading,
+            app.default_style().add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+        )));
+        for (keys, description) in entries {
+            lines.push(Spans::from(vec![
                 Span::styled(
-                    keycode_to_string(keybindings.exit_adding_mode),
-                    app.default_style().add_modifier(Modifier::BOLD),
+                    format!("  {:<width$}  ", keys, width = key_width),
+                    app.key_style(),
                 ),
-                Span::styled(" stop adding | ", app.default_style()),
+                Span::styled(description, app.default_style()),
+            ]));
+        }
+        lines.push(Spans::default());
+    }
+
+    Paragraph::new(lines)
+        .style(app.default_style())
+        .scroll((app.popup_scroll.min(u16::MAX as usize) as u16, 0))
+        .block(
+            Block::default()
+                .title("Key bindings")
+                .borders(Borders::ALL)
+                .border_type(border_type(app))
+                .style(app.default_style()),
+        )
+}
+
+/// Divides the list section into the parts of the statistics screen: the completion gauge on top, the task
+/// count chart and the summary side by side, and the daily completions below.
+pub fn stats_chunks(area: Rect) -> Vec<Rect> {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Length(10), Constraint::Min(3)].as_ref())
+        .split(area);
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Length(32), Constraint::Min(0)].as_ref())
+        .split(rows[1]);
+
+    vec![rows[0], columns[0], columns[1], rows[2]]
+}
+
+/// Provides a gauge of the share of tasks in the list that are completed, `width` columns wide.
+pub fn completion_gauge<'a>(app: &'a App, stats: &Stats, width: u16) -> Gauge<'a> {
+    let total = stats.open + stats.completed;
+    let label = format!("{}/{} done ({:.0}%)", stats.completed, total, stats.done_ratio() * 100.0);
+    // The gauge cannot draw a label wider than itself.
+    let label = if label.width() + 2 <= width as usize { label } else { String::new() };
+
+    Gauge::default()
+        .block(stats_block(app, "Progress"))
+        .style(app.default_style())
+        .gauge_style(app.check_sign_style(false))
+        .ratio(stats.done_ratio())
+        .label(label)
+}
+
+/// Provides a bar chart of the open, completed and archived task `counts`.
+pub fn task_counts<'a>(app: &'a App, counts: &'a [(&'a str, u64)]) -> BarChart<'a> {
+    BarChart::default()
+        .block(stats_block(app, "Tasks"))
+        .data(counts)
+        .bar_width(8)
+        .bar_gap(2)
+        .bar_set(bar_set(app))
+        .bar_style(app.check_sign_style(false))
+        .value_style(app.check_sign_style(false).add_modifier(Modifier::REVERSED))
+        .label_style(app.muted_style(false))
+        .style(app.default_style())
+}
+
+/// Provides the figures of the statistics screen that are not charted.
+pub fn stats_summary<'a>(app: &'a App, stats: &Stats) -> Paragraph<'a> {
+    let days = stats.per_day.len();
+    let period = format!("Completed in the last {} days", days);
+    let completed_in = |days: usize| stats.per_day.iter().rev().take(days).sum::<u64>().to_string();
+    let fields = [
+        ("Average time to complete", stats.average_seconds.map_or("no data".to_string(), format_duration)),
+        ("Completed today", completed_in(1)),
+        ("Completed in the last 7 days", completed_in(7)),
+        (period.as_str(), completed_in(days)),
+        ("Most completed in a day", stats.per_day.iter().max().copied().unwrap_or(0).to_string()),
+    ];
+
+    let label_width = fields.iter().map(|(label, _)| label.width()).max().unwrap_or(0) + 2;
+    let rows: Vec<Spans> = fields
+        .into_iter()
+        .map(|(label, value)| {
+            Spans::from(vec![
+                Span::styled(format!("{:<width$}", label, width = label_width), app.muted_style(false)),
+                Span::styled(value, app.default_style()),
+            ])
+        })
+        .collect();
+
+    Paragraph::new(rows).style(app.default_style()).block(stats_block(app, "Summary"))
+}
+
+/// Spreads the completions of each day over the columns of a sparkline `width` columns wide, borders included.
+/// Returns how many of the latest days fit and the value of each column.
+pub fn day_columns(per_day: &[u64], width: u16) -> (usize, Vec<u64>) {
+    let width = width.saturating_sub(2) as usize;
+    let days = per_day.len().min(width);
+    let columns_per_day = width.checked_div(days).unwrap_or(1);
+    let columns = per_day[per_day.len() - days..]
+        .iter()
+        .flat_map(|&completed| std::iter::repeat_n(completed, columns_per_day))
+        .collect();
+
+    (days, columns)
+}
+
+/// Provides a sparkline of the tasks completed on each of the last `days` days, spread over `columns`.
+pub fn completion_sparkline<'a>(app: &'a App, columns: &'a [u64], days: usize) -> Sparkline<'a> {
+    Sparkline::default()
+        .block(stats_block(app, &format!("Completed per day, last {} days", days)))
+        .data(columns)
+        .bar_set(bar_set(app))
+        .style(app.check_sign_style(false))
+}
+
+/// Returns a bordered block for a part of the statistics screen.
+fn stats_block<'a>(app: &App, title: &str) -> Block<'a> {
+    Block::default()
+        .title(title.to_string())
+        .borders(Borders::ALL)
+        .border_type(border_type(app))
+        .style(app.default_style())
+}
+
+/// Returns the symbols the bars of the charts are drawn with.
+fn bar_set(app: &App) -> symbols::bar::Set {
+    if app.config.settings.ascii {
+        ASCII_BARS
+    } else {
+        symbols::bar::NINE_LEVELS
+    }
+}
+
+/// Divides the command palette popup into the input field and the list of matching commands below it.
+pub fn palette_chunks(area: Rect) -> Vec<Rect> {
+    Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(1)].as_ref())
+        .split(area)
+}
+
+/// Provides the input field of the command palette, where a command name and its argument are typed.
+pub fn palette_input(app: &App) -> Paragraph {
+    let style = app.active_input_style();
+
+    Paragraph::new(app.command_input.as_str()).style(style).block(
+        Block::default()
+            .title("Command")
+            .borders(Borders::ALL)
+            .border_type(border_type(app))
+            .style(style),
+    )
+}
+
+/// Creates the list of commands matching the palette input, each with its description and the keys bound to it
+/// in the mode the palette was opened from.
+pub fn palette_list(app: &App) -> List {
+    let commands = palette::matching_commands(app);
+    let name_width = commands.iter().map(|command| command.name().len()).max().unwrap_or(0);
+
+    let items: Vec<ListItem> = commands
+        .iter()
+        .enumerate()
+        .map(|(index, &command)| {
+            let selected = index == app.selected_command;
+            let style = if selected {
+                app.selection_style()
+            } else {
+                app.default_style()
+            };
+
+            let keys = match command {
+                Command::Action(action) => app
+                    .keymap
+                    .bindings(app.palette_origin)
+                    .filter(|(keys, bound)| *bound == action && !keys.0.is_empty())
+                    .map(|(keys, _)| keys.to_string())
+                    .collect::<Vec<_>>()
+                    .join("/"),
+                Command::Sort | Command::Note | Command::Due => String::new(),
+            };
+
+            ListItem::new(Spans::from(vec![
                 Span::styled(
-                    keycode_to_string(keybindings.save_task),
-                    app.default_style().add_modifier(Modifier::BOLD),
+                    format!("{:<width$}  ", command.name(), width = name_width),
+                    style.add_modifier(Modifier::BOLD),
                 ),
-                Span::styled(" save task", app.default_style()),
-            ],
-            app.default_style(),
-        ),
+                Span::styled(command.description(), style),
+                Span::styled(format!("  {}", keys), app.muted_style(selected)),
+            ]))
+            .style(style)
+        })
+        .collect();
 
-        InputMode::ConfirmQuit => (
-            vec![
-                Span::styled("Press Enter to confirm quitting, 'n' to cancel", app.default_style())
+    List::new(items).style(app.default_style()).block(
+        Block::default()
+            .title(format!("Commands ({})", commands.len()))
+            .borders(Borders::ALL)
+            .border_type(border_type(app))
+            .style(app.default_style()),
+    )
+}
 
-            ],
-            app.default_style()
-        ),
-    };
+/// Creates the confirmation dialog asking whether to perform the action waiting for confirmation, with the keys
+/// that answer it.
+pub fn confirm_dialog(app: &App) -> Paragraph {
+    let kb = &app.config.keybindings;
+    let prompt = app.confirmation.as_ref().map_or("Are you sure?", |confirmation| confirmation.prompt.as_str());
+
+    Paragraph::new(vec![
+        Spans::from(Span::styled(prompt, app.default_style().add_modifier(Modifier::BOLD))),
+        Spans::default(),
+        Spans::from(vec![
+            Span::styled(kb.confirm_yes.to_string(), app.key_style()),
+            Span::styled(" yes   ", app.default_style()),
+            Span::styled(kb.confirm_no.to_string(), app.key_style()),
+            Span::styled(" no", app.default_style()),
+        ]),
+    ])
+    .alignment(Alignment::Center)
+    .style(app.default_style())
+    .block(
+        Block::default()
+            .title("Confirm")
+            .borders(Borders::ALL)
+            .border_type(border_type(app))
+            .style(app.default_style()),
+    )
+}
 
-    let mut help_text = Text::from(Spans::from(msg));
-    help_text.patch_style(style);
-    Paragraph::new(help_text).style(app.default_style())
-}
\ No newline at end of file
+/// Returns the size the confirmation dialog needs to show its prompt and keys in full.
+pub fn confirm_dialog_size(app: &App) -> (u16, u16) {
+    let kb = &app.config.keybindings;
+    let prompt = app.confirmation.as_ref().map_or(0, |confirmation| confirmation.prompt.chars().count());
+    let keys = format!("{} yes   {} no", kb.confirm_yes, kb.confirm_no).chars().count();
+
+    (prompt.max(keys).max(20) as u16 + 6, 5)
+}
+
+/// Returns a rectangle of at most `width` by `height` cells centred in `area`.
+pub fn fixed_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+
+    Rect::new(area.x + (area.width - width) / 2, area.y + (area.height - height) / 2, width, height)
+}
+
+/// Returns a rectangle centred in `area`, taking the given percentages of its width and height.
+pub fn centered_rect(percent_x: u16, percent_y: u16, area: Rect) -> Rect {
+    let vertical = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Percentage((100 - percent_y) / 2),
+                Constraint::Percentage(percent_y),
+                Constraint::Percentage((100 - percent_y) / 2),
+            ]
+            .as_ref(),
+        )
+        .split(area);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(
+            [
+                Constraint::Percentage((100 - percent_x) / 2),
+                Constraint::Percentage(percent_x),
+                Constraint::Percentage((100 - percent_x) / 2),
+            ]
+            .as_ref(),
+        )
+        .split(vertical[1])[1]
+}
+
+/// Returns the tui border type for the configured border style. ASCII borders are drawn as plain lines and
+/// replaced by `AsciiBorders` straight after the widget they surround.
+pub fn border_type(app: &App) -> BorderType {
+    match app.config.glyphs.border {
+        BorderStyle::Rounded => BorderType::Rounded,
+        BorderStyle::Plain | BorderStyle::Ascii => BorderType::Plain,
+        BorderStyle::Double => BorderType::Double,
+        BorderStyle::Thick => BorderType::Thick,
+    }
+}
+
+/// Line symbols drawn with `-`, `|` and `+`, for the ASCII border style.
+pub const ASCII_LINES: symbols::line::Set = symbols::line::Set {
+    vertical: "|",
+    horizontal: "-",
+    top_right: "+",
+    top_left: "+",
+    bottom_right: "+",
+    bottom_left: "+",
+    vertical_left: "+",
+    vertical_right: "+",
+    horizontal_down: "+",
+    horizontal_up: "+",
+    cross: "+",
+};
+
+/// Redraws the plain box-drawing border around its area with `ASCII_LINES`. Rendered over a bordered widget
+/// straight after it, when the border style is ASCII. Only the border is touched: the title on the top border,
+/// which ends at the last cell before the remaining line, and everything inside are left as they are.
+pub struct AsciiBorders;
+
+impl Widget for AsciiBorders {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if area.width < 2 || area.height < 2 {
+            return;
+        }
+
+        let (left, right, top, bottom) = (area.left(), area.right() - 1, area.top(), area.bottom() - 1);
+        let mut swap = |x: u16, y: u16, line: &str, ascii: &'static str| {
+            let cell = buf.get_mut(x, y);
+            if cell.symbol == line {
+                cell.set_symbol(ascii);
+            }
+        };
+
+        for y in top + 1..bottom {
+            swap(left, y, symbols::line::VERTICAL, ASCII_LINES.vertical);
+            swap(right, y, symbols::line::VERTICAL, ASCII_LINES.vertical);
+        }
+        for x in left + 1..right {
+            swap(x, bottom, symbols::line::HORIZONTAL, ASCII_LINES.horizontal);
+        }
+        for (x, y, line) in [
+            (left, top, symbols::line::TOP_LEFT),
+            (right, top, symbols::line::TOP_RIGHT),
+            (left, bottom, symbols::line::BOTTOM_LEFT),
+            (right, bottom, symbols::line::BOTTOM_RIGHT),
+        ] {
+            swap(x, y, line, ASCII_LINES.top_left);
+        }
+
+        // The top border is swapped from the right up to where the title ends.
+        for x in (left + 1..right).rev() {
+            if buf.get(x, top).symbol != symbols::line::HORIZONTAL {
+                break;
+            }
+            buf.get_mut(x, top).set_symbol(ASCII_LINES.horizontal);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ascii_borders_leave_titles_and_contents_alone() {
+        let area = Rect::new(0, 0, 12, 4);
+        let mut buf = Buffer::empty(area);
+        let block = Block::default().borders(Borders::ALL).title("a─b");
+        Paragraph::new(vec![Spans::from("│ └─ x"), Spans::from("──")]).block(block).render(area, &mut buf);
+        AsciiBorders.render(area, &mut buf);
+
+        let rows: Vec<String> = (0..area.height)
+            .map(|y| (0..area.width).map(|x| buf.get(x, y).symbol.as_str()).collect())
+            .collect();
+        assert_eq!(rows, ["+a─b-------+", "|│ └─ x    |", "|──        |", "+----------+"]);
+    }
+}